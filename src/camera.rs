@@ -1,10 +1,43 @@
 use nalgebra_glm::{Vec3, rotate_vec3};
 use std::f32::consts::PI;
+use crate::camera_path::CameraPose;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraMode {
+  /// Orbit around a fixed `center`.
+  Orbit,
+  /// Six degrees of freedom: move the eye freely and look around from it.
+  FreeFly,
+  /// Orbit around a body, keeping the same offset from it while it moves.
+  Follow,
+}
+
+impl CameraMode {
+  pub fn next(self) -> Self {
+    match self {
+      CameraMode::Orbit => CameraMode::FreeFly,
+      CameraMode::FreeFly => CameraMode::Follow,
+      CameraMode::Follow => CameraMode::Orbit,
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      CameraMode::Orbit => "orbit",
+      CameraMode::FreeFly => "free-fly",
+      CameraMode::Follow => "follow",
+    }
+  }
+}
 
+#[derive(Clone)]
 pub struct Camera {
   pub eye: Vec3,
   pub center: Vec3,
   pub up: Vec3,
+  /// Vertical field of view in degrees.
+  pub fov: f32,
+  pub mode: CameraMode,
   pub has_changed: bool
 }
 
@@ -14,6 +47,8 @@ impl Camera {
       eye,
       center,
       up,
+      fov: 45.0,
+      mode: CameraMode::Orbit,
       has_changed: true,
     }
   }
@@ -59,6 +94,20 @@ impl Camera {
     self.has_changed = true;
   }
 
+  /// Multiplies the eye's distance to the center by `factor`, keeping a small
+  /// minimum so the camera never reaches the center.
+  pub fn scale_distance(&mut self, factor: f32) {
+    let offset = self.eye - self.center;
+    let distance = (offset.magnitude() * factor).max(0.2);
+    self.eye = self.center + offset.normalize() * distance;
+    self.has_changed = true;
+  }
+
+  /// Slides eye and center together in the view plane, `dx` to the right and `dy` up.
+  pub fn pan(&mut self, dx: f32, dy: f32) {
+    self.fly(Vec3::new(dx, dy, 0.0));
+  }
+
   pub fn move_center(&mut self, direction: Vec3) {
     let radius_vector = self.center - self.eye;
     let radius = radius_vector.magnitude();
@@ -74,5 +123,71 @@ impl Camera {
     self.center = self.eye + final_rotated.normalize() * radius;
     self.has_changed = true;
   }
-}
 
+  /// Moves eye and center together along the camera's own axes:
+  /// `x` is right, `y` is up and `z` is forward.
+  pub fn fly(&mut self, movement: Vec3) {
+    let (forward, right, up) = self.axes();
+
+    let offset = right * movement.x + up * movement.y + forward * movement.z;
+    self.eye += offset;
+    self.center += offset;
+    self.up = up;
+    self.has_changed = true;
+  }
+
+  /// Turns the view around the eye. Yaw spins around the camera's own up
+  /// axis and pitch around its right axis, so there are no poles: pitching
+  /// past vertical keeps going and turns the camera upside down.
+  pub fn look(&mut self, delta_yaw: f32, delta_pitch: f32) {
+    let distance = (self.center - self.eye).magnitude();
+    let (forward, right, up) = self.axes();
+
+    let forward = rotate_vec3(&forward, -delta_yaw, &up);
+    let forward = rotate_vec3(&forward, delta_pitch, &right).normalize();
+
+    self.center = self.eye + forward * distance;
+    self.up = right.cross(&forward).normalize();
+    self.has_changed = true;
+  }
+
+  /// Rolls the camera around its view direction; positive angles tilt the
+  /// top of the view to the right.
+  pub fn roll(&mut self, angle: f32) {
+    let (forward, _, up) = self.axes();
+    self.up = rotate_vec3(&up, angle, &forward).normalize();
+    self.has_changed = true;
+  }
+
+  /// Turns `up` back to world +Y, undoing any roll from free-fly. A view
+  /// straight up or down is first tilted back into the orbit pitch range.
+  pub fn level(&mut self) {
+    self.up = Vec3::new(0.0, 1.0, 0.0);
+    self.orbit(0.0, 0.0);
+  }
+
+  /// Orthonormal forward, right and up vectors of the current orientation.
+  fn axes(&self) -> (Vec3, Vec3, Vec3) {
+    let forward = (self.center - self.eye).normalize();
+    let right = forward.cross(&self.up).normalize();
+    let up = right.cross(&forward).normalize();
+    (forward, right, up)
+  }
+
+  /// Re-centers on `target` while keeping the current eye offset, so the
+  /// camera travels along with a moving body.
+  pub fn follow(&mut self, target: Vec3) {
+    let offset = self.eye - self.center;
+    self.center = target;
+    self.eye = target + offset;
+    self.has_changed = true;
+  }
+
+  pub fn apply_pose(&mut self, pose: &CameraPose) {
+    self.eye = pose.eye;
+    self.center = pose.center;
+    self.up = pose.up;
+    self.fov = pose.fov;
+    self.has_changed = true;
+  }
+}
@@ -0,0 +1,246 @@
+// camera_path.rs
+//
+// Scripted camera fly-throughs. Keyframes are read from a TOML file:
+//
+//     interpolation = "catmull-rom"   # or "hermite"
+//     looping = false
+//
+//     [[keyframe]]
+//     time = 0.0
+//     eye = [0.0, 0.0, 5.0]
+//     center = [0.0, 0.0, 0.0]
+//     up = [0.0, 1.0, 0.0]            # optional, defaults to +Y
+//     fov = 45.0                      # optional, degrees
+//     eye_tangent = [1.0, 0.0, 0.0]   # optional, hermite only, units per second
+//     center_tangent = [0.0, 0.0, 0.0]
+
+use nalgebra_glm::Vec3;
+use nalgebra::UnitQuaternion;
+use serde::Deserialize;
+use std::fmt;
+
+const DEFAULT_FOV: f32 = 45.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interpolation {
+    /// Tangents come from the neighbouring keyframes.
+    CatmullRom,
+    /// Tangents come from each keyframe's `eye_tangent` and `center_tangent`,
+    /// falling back to Catmull-Rom tangents where they are not given.
+    Hermite,
+}
+
+#[derive(Clone, Debug)]
+pub struct Keyframe {
+    pub time: f32,
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub up: Vec3,
+    pub fov: f32,
+    pub eye_tangent: Option<Vec3>,
+    pub center_tangent: Option<Vec3>,
+}
+
+/// Camera state sampled from a path.
+#[derive(Clone, Debug)]
+pub struct CameraPose {
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub up: Vec3,
+    pub fov: f32,
+}
+
+pub struct CameraPath {
+    pub keyframes: Vec<Keyframe>,
+    pub interpolation: Interpolation,
+    pub looping: bool,
+}
+
+#[derive(Debug)]
+pub enum CameraPathError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for CameraPathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CameraPathError::Io(err) => write!(f, "could not read camera path: {}", err),
+            CameraPathError::Parse(err) => write!(f, "invalid camera path file: {}", err),
+            CameraPathError::Invalid(message) => write!(f, "invalid camera path: {}", message),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathFile {
+    #[serde(default = "default_interpolation")]
+    interpolation: Interpolation,
+    #[serde(default)]
+    looping: bool,
+    #[serde(rename = "keyframe")]
+    keyframes: Vec<KeyframeFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyframeFile {
+    time: f32,
+    eye: [f32; 3],
+    center: [f32; 3],
+    up: Option<[f32; 3]>,
+    fov: Option<f32>,
+    eye_tangent: Option<[f32; 3]>,
+    center_tangent: Option<[f32; 3]>,
+}
+
+fn default_interpolation() -> Interpolation {
+    Interpolation::CatmullRom
+}
+
+fn vec3(v: [f32; 3]) -> Vec3 {
+    Vec3::new(v[0], v[1], v[2])
+}
+
+/// Orientation looking from `eye` towards `center`, with `up` as the roll reference.
+fn orientation(eye: &Vec3, center: &Vec3, up: &Vec3) -> UnitQuaternion<f32> {
+    UnitQuaternion::face_towards(&(center - eye), up)
+}
+
+/// Cubic Hermite interpolation between `p0` and `p1` over a segment of length `dt`,
+/// with tangents `m0` and `m1` given per unit of time.
+fn hermite(p0: &Vec3, m0: &Vec3, p1: &Vec3, m1: &Vec3, dt: f32, s: f32) -> Vec3 {
+    let s2 = s * s;
+    let s3 = s2 * s;
+    let h00 = 2.0 * s3 - 3.0 * s2 + 1.0;
+    let h10 = s3 - 2.0 * s2 + s;
+    let h01 = -2.0 * s3 + 3.0 * s2;
+    let h11 = s3 - s2;
+    p0 * h00 + m0 * (h10 * dt) + p1 * h01 + m1 * (h11 * dt)
+}
+
+impl Keyframe {
+    fn pose(&self) -> CameraPose {
+        CameraPose { eye: self.eye, center: self.center, up: self.up, fov: self.fov }
+    }
+}
+
+impl CameraPath {
+    pub fn load(filename: &str) -> Result<Self, CameraPathError> {
+        let text = std::fs::read_to_string(filename).map_err(CameraPathError::Io)?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, CameraPathError> {
+        let file: PathFile = toml::from_str(text).map_err(CameraPathError::Parse)?;
+
+        let keyframes: Vec<Keyframe> = file.keyframes.into_iter().map(|key| Keyframe {
+            time: key.time,
+            eye: vec3(key.eye),
+            center: vec3(key.center),
+            up: key.up.map(vec3).unwrap_or(Vec3::new(0.0, 1.0, 0.0)),
+            fov: key.fov.unwrap_or(DEFAULT_FOV),
+            eye_tangent: key.eye_tangent.map(vec3),
+            center_tangent: key.center_tangent.map(vec3),
+        }).collect();
+
+        if keyframes.is_empty() {
+            return Err(CameraPathError::Invalid("at least one keyframe is required".to_string()));
+        }
+        for (index, pair) in keyframes.windows(2).enumerate() {
+            if pair[1].time <= pair[0].time {
+                return Err(CameraPathError::Invalid(format!(
+                    "keyframe {} has time {} which is not after the previous keyframe's {}",
+                    index + 1, pair[1].time, pair[0].time,
+                )));
+            }
+        }
+        for (index, key) in keyframes.iter().enumerate() {
+            if (key.center - key.eye).magnitude() < f32::EPSILON {
+                return Err(CameraPathError::Invalid(format!("keyframe {} has the same eye and center", index)));
+            }
+        }
+
+        Ok(CameraPath {
+            keyframes,
+            interpolation: file.interpolation,
+            looping: file.looping,
+        })
+    }
+
+    pub fn duration(&self) -> f32 {
+        let first = self.keyframes.first().map_or(0.0, |key| key.time);
+        let last = self.keyframes.last().map_or(0.0, |key| key.time);
+        last - first
+    }
+
+    /// Catmull-Rom tangent at keyframe `i` of the value picked by `value`, per unit of time.
+    fn catmull_rom_tangent(&self, i: usize, value: impl Fn(&Keyframe) -> Vec3) -> Vec3 {
+        let last = self.keyframes.len() - 1;
+        let prev = &self.keyframes[i.saturating_sub(1)];
+        let next = &self.keyframes[(i + 1).min(last)];
+        let dt = next.time - prev.time;
+        if dt <= 0.0 {
+            Vec3::new(0.0, 0.0, 0.0)
+        } else {
+            (value(next) - value(prev)) / dt
+        }
+    }
+
+    fn eye_tangent(&self, i: usize) -> Vec3 {
+        match (self.interpolation, self.keyframes[i].eye_tangent) {
+            (Interpolation::Hermite, Some(tangent)) => tangent,
+            _ => self.catmull_rom_tangent(i, |key| key.eye),
+        }
+    }
+
+    fn center_tangent(&self, i: usize) -> Vec3 {
+        match (self.interpolation, self.keyframes[i].center_tangent) {
+            (Interpolation::Hermite, Some(tangent)) => tangent,
+            _ => self.catmull_rom_tangent(i, |key| key.center),
+        }
+    }
+
+    /// Camera pose at `time` seconds from the start of the path. Eye and center
+    /// follow their splines, so the camera stays aimed at the scripted target,
+    /// while the roll comes from slerping the keyframe orientations.
+    pub fn sample(&self, time: f32) -> CameraPose {
+        let first = &self.keyframes[0];
+        let mut t = first.time + time;
+        if self.looping && self.duration() > 0.0 {
+            t = first.time + time.rem_euclid(self.duration());
+        }
+
+        if t <= first.time {
+            return first.pose();
+        }
+        let i = match self.keyframes.windows(2).position(|pair| t < pair[1].time) {
+            Some(i) => i,
+            None => return self.keyframes[self.keyframes.len() - 1].pose(),
+        };
+
+        let k0 = &self.keyframes[i];
+        let k1 = &self.keyframes[i + 1];
+        let dt = k1.time - k0.time;
+        let s = (t - k0.time) / dt;
+
+        let eye = hermite(&k0.eye, &self.eye_tangent(i), &k1.eye, &self.eye_tangent(i + 1), dt, s);
+        let center = hermite(&k0.center, &self.center_tangent(i), &k1.center, &self.center_tangent(i + 1), dt, s);
+
+        // Opposite orientations have no unique slerp; keep the first one's roll then
+        let from = orientation(&k0.eye, &k0.center, &k0.up);
+        let to = orientation(&k1.eye, &k1.center, &k1.up);
+        let rotation = from.try_slerp(&to, s, 1.0e-6).unwrap_or(from);
+        let up = rotation * Vec3::new(0.0, 1.0, 0.0);
+
+        CameraPose {
+            eye,
+            center,
+            up,
+            fov: k0.fov + (k1.fov - k0.fov) * s,
+        }
+    }
+}
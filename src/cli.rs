@@ -0,0 +1,164 @@
+// cli.rs
+//
+// Command-line options of the viewer. Without any of the mode options
+// (--record, --headless, --export-mesh, ...) it opens the window.
+
+use clap::{ArgGroup, Parser};
+use nalgebra_glm::Vec3;
+use std::path::PathBuf;
+use shaders_lab4::normals::NormalMode;
+use shaders_lab4::scene_file;
+use shaders_lab4::scene::{planet_name, SELECTABLE_BODIES};
+
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about = "Software renderer for the planets of a solar system",
+    after_help = "Vectors are written as x,y,z, e.g. --eye 0,2,6 or --eye=-3,1,4.",
+    group(ArgGroup::new("mode").args(["list_keys", "bless_golden", "check_golden", "export_mesh", "record", "headless"])),
+)]
+pub struct Args {
+    /// Planet to start with, by name or number (0 Neptune, 1 Jupiter, ..., 8 Sun)
+    #[arg(long, value_name = "BODY", default_value = "neptune", value_parser = parse_body)]
+    pub body: u8,
+
+    /// Scene file (TOML) to show instead of a single planet
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["body", "model"])]
+    pub scene: Option<String>,
+
+    /// Model (OBJ, glTF, PLY or STL) that replaces the planet; file.obj#Part loads only that part
+    #[arg(long, value_name = "FILE")]
+    pub model: Option<String>,
+
+    /// Directory with sphere.obj and ring.obj
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    pub assets: PathBuf,
+
+    /// How to get vertex normals: file, flat, smooth or smooth:<degrees>
+    #[arg(long, value_name = "MODE", default_value = "file", value_parser = parse_normals)]
+    pub normals: NormalMode,
+
+    /// Window width, also used for recordings
+    #[arg(long, default_value_t = 600, value_parser = clap::value_parser!(u32).range(1..))]
+    pub width: u32,
+
+    /// Window height, also used for recordings
+    #[arg(long, default_value_t = 600, value_parser = clap::value_parser!(u32).range(1..))]
+    pub height: u32,
+
+    /// Background color as RRGGBB hex [default: the scene file's, or 333355]
+    #[arg(long, value_name = "COLOR", value_parser = parse_color)]
+    pub background: Option<u32>,
+
+    /// Simulation time to start at, in seconds
+    #[arg(long, value_name = "SECONDS", default_value_t = 0.0, allow_hyphen_values = true)]
+    pub time: f32,
+
+    /// Camera position [default: the scene file's, or 0,0,5]
+    #[arg(long, value_name = "X,Y,Z", value_parser = parse_vec3, allow_hyphen_values = true)]
+    pub eye: Option<Vec3>,
+
+    /// Point the camera looks at [default: the scene file's, or 0,0,0]
+    #[arg(long, value_name = "X,Y,Z", value_parser = parse_vec3, allow_hyphen_values = true)]
+    pub target: Option<Vec3>,
+
+    /// Camera up direction [default: the scene file's, or 0,1,0]
+    #[arg(long, value_name = "X,Y,Z", value_parser = parse_vec3, allow_hyphen_values = true)]
+    pub up: Option<Vec3>,
+
+    /// Vertical field of view in degrees, between 0 and 180 [default: the scene file's, or 45]
+    #[arg(long, value_name = "DEGREES", value_parser = parse_fov)]
+    pub fov: Option<f32>,
+
+    /// Key bindings file [default: keybindings.toml when it exists]
+    #[arg(long, value_name = "FILE")]
+    pub keybindings: Option<String>,
+
+    /// Camera path (TOML keyframes) to play with P, or to render with --headless or --record
+    #[arg(long, value_name = "FILE")]
+    pub camera_path: Option<String>,
+
+    /// Print every action with its default keys and exit
+    #[arg(long)]
+    pub list_keys: bool,
+
+    /// Write the starting body's mesh to an .obj or .ply file and exit
+    #[arg(long, value_name = "FILE")]
+    pub export_mesh: Option<String>,
+
+    /// Bake the fragment shader colors into the exported vertices
+    #[arg(long, requires = "export_mesh")]
+    pub bake_colors: bool,
+
+    /// Render a fixed number of frames without a window to a .gif, a .y4m or a directory of PNGs
+    #[arg(long, value_name = "TARGET")]
+    pub record: Option<String>,
+
+    /// Frames to record
+    #[arg(long, default_value_t = 120, requires = "record", value_parser = clap::value_parser!(u32).range(1..))]
+    pub frames: u32,
+
+    /// Simulated seconds between recorded frames [default: 1/fps]
+    #[arg(long, value_name = "SECONDS", requires = "record", value_parser = parse_positive)]
+    pub timestep: Option<f32>,
+
+    /// Playback rate of recordings and --headless renders
+    #[arg(long, default_value_t = 30.0, value_parser = parse_positive)]
+    pub fps: f32,
+
+    /// Render the whole --camera-path without a window to a .gif, a .y4m or a directory of PNGs
+    #[arg(long, value_name = "TARGET", requires = "camera_path")]
+    pub headless: Option<String>,
+
+    /// Compare every planet with its reference image in tests/golden and exit
+    #[arg(long)]
+    pub check_golden: bool,
+
+    /// Overwrite the reference images in tests/golden with the current renders
+    #[arg(long)]
+    pub bless_golden: bool,
+}
+
+fn parse_body(text: &str) -> Result<u8, String> {
+    SELECTABLE_BODIES
+        .into_iter()
+        .find(|&body| text == body.to_string() || text.eq_ignore_ascii_case(planet_name(body)))
+        .ok_or_else(|| {
+            let names: Vec<String> = SELECTABLE_BODIES.iter().map(|&body| planet_name(body).to_lowercase()).collect();
+            format!("expected one of {} or their numbers 0-8", names.join(", "))
+        })
+}
+
+fn parse_normals(text: &str) -> Result<NormalMode, String> {
+    NormalMode::parse(text).ok_or_else(|| "expected file, flat, smooth or smooth:<degrees>".to_string())
+}
+
+fn parse_color(text: &str) -> Result<u32, String> {
+    scene_file::parse_color(text).ok_or_else(|| "expected a hex color like 333355".to_string())
+}
+
+fn parse_positive(text: &str) -> Result<f32, String> {
+    match text.parse::<f32>() {
+        Ok(value) if value > 0.0 && value.is_finite() => Ok(value),
+        _ => Err("expected a positive number".to_string()),
+    }
+}
+
+fn parse_fov(text: &str) -> Result<f32, String> {
+    match text.parse::<f32>() {
+        Ok(degrees) if degrees > 0.0 && degrees < 180.0 => Ok(degrees),
+        _ => Err("expected an angle between 0 and 180 degrees, both excluded".to_string()),
+    }
+}
+
+fn parse_vec3(text: &str) -> Result<Vec3, String> {
+    let components: Vec<f32> = text
+        .split(',')
+        .map(|component| component.trim().parse::<f32>())
+        .collect::<Result<_, _>>()
+        .map_err(|err| err.to_string())?;
+    match components[..] {
+        [x, y, z] => Ok(Vec3::new(x, y, z)),
+        _ => Err("expected three numbers separated by commas".to_string()),
+    }
+}
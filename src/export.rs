@@ -0,0 +1,195 @@
+// export.rs
+//
+// Writes a body's mesh, as the renderer sees it, to OBJ or PLY so it can be
+// opened in other tools. Terrain displacement is applied and the vertices
+// are welded back together, since our meshes are stored one vertex per
+// triangle corner.
+
+use nalgebra_glm::{Mat4, Vec2, Vec3};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::scene::Scene;
+use crate::vertex::Vertex;
+use crate::shaders::ShaderRegistry;
+use crate::{Uniforms, TICKS_PER_SECOND};
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(io::Error),
+    UnknownFormat(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::Io(err) => write!(f, "could not write mesh: {}", err),
+            ExportError::UnknownFormat(extension) => {
+                write!(f, "unknown mesh format \"{}\", expected obj or ply", extension)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for ExportError {
+    fn from(err: io::Error) -> Self {
+        ExportError::Io(err)
+    }
+}
+
+/// Object-space vertices of body `index` at full detail, displaced like the
+/// vertex shader does. With `bake_colors` every vertex also gets the color its
+/// fragment shader gives it at `sim_time`, unlit.
+pub fn body_mesh(scene: &Scene, meshes: &[Vec<Vertex>], shaders: &ShaderRegistry, index: usize, sim_time: f32, bake_colors: bool) -> Vec<Vertex> {
+    let body = &scene.bodies[index];
+    let shader = shaders.for_body(body);
+    let uniforms = Uniforms {
+        model_matrix: Mat4::identity(),
+        view_matrix: Mat4::identity(),
+        projection_matrix: Mat4::identity(),
+        viewport_matrix: Mat4::identity(),
+        time: (sim_time * TICKS_PER_SECOND) as u32,
+        material: body.material.clone(),
+        displacement: body.displacement,
+        lights: Vec::new(),
+        camera_position: Vec3::zeros(),
+    };
+
+    meshes[body.mesh].iter().map(|vertex| {
+        let mut vertex = vertex.clone();
+        if let Some(displacement) = &body.displacement {
+            (vertex.position, vertex.normal) = displacement.displace(&vertex.position, &vertex.normal);
+        }
+        if bake_colors {
+            let fragment = Fragment::new(
+                Vec2::zeros(), vertex.color, 0.0, vertex.normal, 1.0, vertex.position, vertex.tex_coords,
+            );
+            vertex.color = shader(&fragment, &uniforms).clamp();
+        }
+        vertex
+    }).collect()
+}
+
+/// Writes `vertices`, three per triangle, to `filename` in the format its
+/// extension names. Returns the number of vertices after welding.
+pub fn write_mesh(filename: &str, vertices: &[Vertex], with_colors: bool) -> Result<usize, ExportError> {
+    let extension = Path::new(filename)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let write = match extension.as_str() {
+        "obj" => write_obj,
+        "ply" => write_ply,
+        _ => return Err(ExportError::UnknownFormat(extension)),
+    };
+    let (unique, indices) = weld(vertices);
+    let mut out = BufWriter::new(File::create(filename)?);
+    write(&mut out, &unique, &indices, with_colors)?;
+    out.flush()?;
+    Ok(unique.len())
+}
+
+/// Writes body `index` as drawn at `sim_time`, with its shader colors baked
+/// into the vertices, to `export_<name>.obj` and `export_<name>.ply` in the
+/// working directory, reporting each file written.
+pub fn export_body(scene: &Scene, meshes: &[Vec<Vertex>], shaders: &ShaderRegistry, index: usize, sim_time: f32) {
+    let name: String = scene.bodies[index].name.to_lowercase().chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let vertices = body_mesh(scene, meshes, shaders, index, sim_time, true);
+    for extension in ["obj", "ply"] {
+        let filename = format!("export_{}.{}", name, extension);
+        match write_mesh(&filename, &vertices, true) {
+            Ok(count) => println!("Wrote {} ({} vertices)", filename, count),
+            Err(err) => eprintln!("{}: {}", filename, err),
+        }
+    }
+}
+
+/// Merges corners with the same position, normal, UV and color.
+fn weld(vertices: &[Vertex]) -> (Vec<Vertex>, Vec<u32>) {
+    let mut unique: Vec<Vertex> = Vec::new();
+    let mut lookup: HashMap<[u32; 11], u32> = HashMap::new();
+    let indices = vertices.iter().map(|vertex| {
+        let (p, n, uv, c) = (vertex.position, vertex.normal, vertex.tex_coords, vertex.color);
+        let key = [p.x, p.y, p.z, n.x, n.y, n.z, uv.x, uv.y, c.r, c.g, c.b].map(f32::to_bits);
+        *lookup.entry(key).or_insert_with(|| {
+            unique.push(vertex.clone());
+            unique.len() as u32 - 1
+        })
+    }).collect();
+    (unique, indices)
+}
+
+/// Color as 0-255 channels; shader colors can run past 255 before clamping.
+fn color_bytes(color: &Color) -> [u8; 3] {
+    [color.r, color.g, color.b].map(|channel| channel.round().clamp(0.0, 255.0) as u8)
+}
+
+/// Vertex colors go after the position (`v x y z r g b`, 0-1), the extension
+/// most tools read. UVs are flipped back to OBJ's bottom-left origin.
+fn write_obj(out: &mut BufWriter<File>, vertices: &[Vertex], indices: &[u32], with_colors: bool) -> io::Result<()> {
+    writeln!(out, "# Exported by shaders-lab4")?;
+    for vertex in vertices {
+        let p = vertex.position;
+        if with_colors {
+            let [r, g, b] = color_bytes(&vertex.color);
+            writeln!(out, "v {} {} {} {:.4} {:.4} {:.4}", p.x, p.y, p.z, r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)?;
+        } else {
+            writeln!(out, "v {} {} {}", p.x, p.y, p.z)?;
+        }
+    }
+    for vertex in vertices {
+        writeln!(out, "vt {} {}", vertex.tex_coords.x, 1.0 - vertex.tex_coords.y)?;
+    }
+    for vertex in vertices {
+        let n = vertex.normal;
+        writeln!(out, "vn {} {} {}", n.x, n.y, n.z)?;
+    }
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0] + 1, triangle[1] + 1, triangle[2] + 1];
+        writeln!(out, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+    }
+    Ok(())
+}
+
+/// Binary little-endian PLY with the property names `ply::load` reads back.
+fn write_ply(out: &mut BufWriter<File>, vertices: &[Vertex], indices: &[u32], with_colors: bool) -> io::Result<()> {
+    writeln!(out, "ply")?;
+    writeln!(out, "format binary_little_endian 1.0")?;
+    writeln!(out, "comment Exported by shaders-lab4")?;
+    writeln!(out, "element vertex {}", vertices.len())?;
+    for property in ["x", "y", "z", "nx", "ny", "nz", "s", "t"] {
+        writeln!(out, "property float {}", property)?;
+    }
+    if with_colors {
+        for property in ["red", "green", "blue"] {
+            writeln!(out, "property uchar {}", property)?;
+        }
+    }
+    writeln!(out, "element face {}", indices.len() / 3)?;
+    writeln!(out, "property list uchar uint vertex_indices")?;
+    writeln!(out, "end_header")?;
+
+    for vertex in vertices {
+        let (p, n) = (vertex.position, vertex.normal);
+        let values = [p.x, p.y, p.z, n.x, n.y, n.z, vertex.tex_coords.x, 1.0 - vertex.tex_coords.y];
+        for value in values {
+            out.write_all(&value.to_le_bytes())?;
+        }
+        if with_colors {
+            out.write_all(&color_bytes(&vertex.color))?;
+        }
+    }
+    for triangle in indices.chunks_exact(3) {
+        out.write_all(&[3])?;
+        for index in triangle {
+            out.write_all(&index.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
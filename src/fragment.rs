@@ -10,10 +10,11 @@ pub struct Fragment {
     pub normal: Vec3,
     pub intensity: f32,
     pub vertex_position: Vec3,
+    pub tex_coords: Vec2,
 }
 
 impl Fragment {
-    pub fn new(position: Vec2, color: Color, depth: f32, normal: Vec3, intensity: f32, vertex_position: Vec3) -> Self {
+    pub fn new(position: Vec2, color: Color, depth: f32, normal: Vec3, intensity: f32, vertex_position: Vec3, tex_coords: Vec2) -> Self {
         Fragment {
             position,
             color,
@@ -21,6 +22,7 @@ impl Fragment {
             normal,
             intensity,
             vertex_position,
+            tex_coords,
         }
     }
 }
\ No newline at end of file
@@ -1,3 +1,12 @@
+use nalgebra_glm::Vec3;
+use crate::font;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextAlign {
+	Left,
+	Center,
+	Right,
+}
 pub struct Framebuffer {
 	pub width: usize,
 	pub height: usize,
@@ -19,6 +28,14 @@ impl Framebuffer {
 		}
 	}
 
+	/// Reallocates both buffers for a new size. The contents are cleared.
+	pub fn resize(&mut self, width: usize, height: usize) {
+		self.width = width;
+		self.height = height;
+		self.buffer = vec![self.background_color; width * height];
+		self.zbuffer = vec![f32::INFINITY; width * height];
+	}
+
 	pub fn clear(&mut self) {
 		for pixel in self.buffer.iter_mut() {
 			*pixel = self.background_color;
@@ -38,6 +55,17 @@ impl Framebuffer {
 		}
 	}
 
+	/// Depth-tested like `point`, but mixes the current color in with `alpha`
+	/// and leaves the depth alone so surfaces behind stay visible.
+	pub fn blend_point(&mut self, x: usize, y: usize, depth: f32, alpha: f32) {
+		if x < self.width && y < self.height {
+			let index = y * self.width + x;
+			if self.zbuffer[index] > depth {
+				self.blend_pixel(index, self.current_color, alpha);
+			}
+		}
+	}
+
 	pub fn set_background_color(&mut self, color: u32) {
 		self.background_color = color;
 	}
@@ -45,4 +73,235 @@ impl Framebuffer {
 	pub fn set_current_color(&mut self, color: u32) {
 		self.current_color = color;
 	}
-}
\ No newline at end of file
+
+	/// Color buffer as tightly packed RGB bytes, the layout image encoders expect.
+	pub fn to_rgb_bytes(&self) -> Vec<u8> {
+		let mut bytes = Vec::with_capacity(self.buffer.len() * 3);
+		for pixel in &self.buffer {
+			bytes.push((pixel >> 16) as u8);
+			bytes.push((pixel >> 8) as u8);
+			bytes.push(*pixel as u8);
+		}
+		bytes
+	}
+
+	/// Writes a pixel straight into the color buffer, ignoring depth. Used for overlays.
+	pub fn set_pixel(&mut self, x: i32, y: i32, color: u32) {
+		if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+			let index = y as usize * self.width + x as usize;
+			self.buffer[index] = color;
+		}
+	}
+
+	/// Size in pixels of `text` drawn at `scale`, accounting for multiple lines.
+	pub fn text_size(text: &str, scale: usize) -> (usize, usize) {
+		let width = text.lines().map(font::line_width).max().unwrap_or(0);
+		let lines = text.lines().count();
+		let height = if lines == 0 { 0 } else { (lines - 1) * font::LINE_HEIGHT + font::GLYPH_HEIGHT };
+		(width * scale, height * scale)
+	}
+
+	/// Draws `text` with the built-in bitmap font on top of the current image.
+	/// `x` is the left edge, center or right edge of each line depending on `align`;
+	/// `y` is the top of the first line.
+	pub fn draw_text(&mut self, x: i32, y: i32, text: &str, color: u32, scale: usize, align: TextAlign) {
+		let scale = scale.max(1);
+		for (line_index, line) in text.lines().enumerate() {
+			let line_width = (font::line_width(line) * scale) as i32;
+			let start_x = match align {
+				TextAlign::Left => x,
+				TextAlign::Center => x - line_width / 2,
+				TextAlign::Right => x - line_width,
+			};
+			let line_y = y + (line_index * font::LINE_HEIGHT * scale) as i32;
+
+			for (char_index, ch) in line.chars().enumerate() {
+				let glyph_x = start_x + (char_index * font::ADVANCE * scale) as i32;
+				for (row, bits) in font::glyph(ch).iter().enumerate() {
+					for column in 0..font::GLYPH_WIDTH {
+						if bits & (1 << (font::GLYPH_WIDTH - 1 - column)) == 0 {
+							continue;
+						}
+						for sy in 0..scale {
+							for sx in 0..scale {
+								self.set_pixel(
+									glyph_x + (column * scale + sx) as i32,
+									line_y + (row * scale + sy) as i32,
+									color,
+								);
+							}
+						}
+					}
+				}
+			}
+		}
+	}
+
+	/// Mixes `color` into the pixel at `index` with the given opacity.
+	fn blend_pixel(&mut self, index: usize, color: u32, alpha: f32) {
+		let alpha = alpha.clamp(0.0, 1.0);
+		let dst = self.buffer[index];
+		let mix = |shift: u32| {
+			let d = ((dst >> shift) & 0xFF) as f32;
+			let c = ((color >> shift) & 0xFF) as f32;
+			((d + (c - d) * alpha).round() as u32) << shift
+		};
+		self.buffer[index] = mix(16) | mix(8) | mix(0);
+	}
+
+	/// Blends a line pixel if it passes the depth test. Lines read the
+	/// zbuffer but never write it, so they stay overlays on top of the meshes.
+	fn line_pixel(&mut self, x: i32, y: i32, depth: f32, color: u32, alpha: f32) {
+		if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+			return;
+		}
+		let index = y as usize * self.width + x as usize;
+		if depth < self.zbuffer[index] {
+			self.blend_pixel(index, color, alpha);
+		}
+	}
+
+	/// Cuts the segment to the framebuffer, plus a pixel of margin for the
+	/// anti-aliased edges, with Cohen-Sutherland clipping. Depth is
+	/// interpolated at the cut points. Returns `None` when nothing is left.
+	fn clip_line(&self, mut from: Vec3, mut to: Vec3) -> Option<(Vec3, Vec3)> {
+		const LEFT: u8 = 1;
+		const RIGHT: u8 = 2;
+		const TOP: u8 = 4;
+		const BOTTOM: u8 = 8;
+		if !from.iter().chain(to.iter()).all(|c| c.is_finite()) {
+			return None;
+		}
+		let (x_min, y_min) = (-1.0, -1.0);
+		let (x_max, y_max) = (self.width as f32, self.height as f32);
+		let outcode = |p: &Vec3| {
+			let mut code = 0;
+			if p.x < x_min { code |= LEFT } else if p.x > x_max { code |= RIGHT }
+			if p.y < y_min { code |= TOP } else if p.y > y_max { code |= BOTTOM }
+			code
+		};
+
+		let (mut code_from, mut code_to) = (outcode(&from), outcode(&to));
+		loop {
+			if code_from | code_to == 0 {
+				return Some((from, to));
+			}
+			if code_from & code_to != 0 {
+				return None;
+			}
+			let code = if code_from != 0 { code_from } else { code_to };
+			let d = to - from;
+			// The segment crosses the edge in `code`, so the divisions below are by non-zero values
+			let t = if code & LEFT != 0 {
+				(x_min - from.x) / d.x
+			} else if code & RIGHT != 0 {
+				(x_max - from.x) / d.x
+			} else if code & TOP != 0 {
+				(y_min - from.y) / d.y
+			} else {
+				(y_max - from.y) / d.y
+			};
+			let mut point = from + d * t;
+			// Snap to the edge exactly so rounding can't leave the point just outside
+			if code & LEFT != 0 { point.x = x_min } else if code & RIGHT != 0 { point.x = x_max }
+			else if code & TOP != 0 { point.y = y_min } else { point.y = y_max }
+			if code == code_from {
+				from = point;
+				code_from = outcode(&from);
+			} else {
+				to = point;
+				code_to = outcode(&to);
+			}
+		}
+	}
+
+	/// Aliased line using Bresenham's algorithm. `from` and `to` are screen
+	/// positions with depth in `z`; depth is interpolated along the line.
+	pub fn line(&mut self, from: Vec3, to: Vec3, color: u32, alpha: f32) {
+		let Some((from, to)) = self.clip_line(from, to) else {
+			return;
+		};
+		let (mut x, mut y) = (from.x.round() as i32, from.y.round() as i32);
+		let (x1, y1) = (to.x.round() as i32, to.y.round() as i32);
+		let dx = (x1 - x).abs();
+		let dy = -(y1 - y).abs();
+		let step_x = if x < x1 { 1 } else { -1 };
+		let step_y = if y < y1 { 1 } else { -1 };
+		let steps = dx.max(-dy).max(1) as f32;
+		let mut error = dx + dy;
+		let mut step = 0.0;
+
+		loop {
+			let depth = from.z + (to.z - from.z) * (step / steps);
+			self.line_pixel(x, y, depth, color, alpha);
+			if x == x1 && y == y1 {
+				break;
+			}
+			let doubled = 2 * error;
+			if doubled >= dy {
+				error += dy;
+				x += step_x;
+			}
+			if doubled <= dx {
+				error += dx;
+				y += step_y;
+			}
+			step += 1.0;
+		}
+	}
+
+	/// Anti-aliased line using Xiaolin Wu's algorithm, with the same depth
+	/// handling as `line`.
+	pub fn line_aa(&mut self, from: Vec3, to: Vec3, color: u32, alpha: f32) {
+		let Some((from, to)) = self.clip_line(from, to) else {
+			return;
+		};
+		let steep = (to.y - from.y).abs() > (to.x - from.x).abs();
+		let (mut a, mut b) = (from, to);
+		if steep {
+			a = Vec3::new(a.y, a.x, a.z);
+			b = Vec3::new(b.y, b.x, b.z);
+		}
+		if a.x > b.x {
+			std::mem::swap(&mut a, &mut b);
+		}
+
+		let dx = b.x - a.x;
+		let gradient = if dx.abs() < f32::EPSILON { 1.0 } else { (b.y - a.y) / dx };
+		let depth_at = |x: f32| if dx.abs() < f32::EPSILON { a.z } else { a.z + (b.z - a.z) * ((x - a.x) / dx) };
+
+		// Plots the pair of pixels straddling the ideal line at column `x`.
+		let plot = |fb: &mut Self, x: i32, y: f32, coverage: f32| {
+			let depth = depth_at(x as f32);
+			let y_floor = y.floor();
+			let fraction = y - y_floor;
+			let (px, py) = if steep { (y_floor as i32, x) } else { (x, y_floor as i32) };
+			let (qx, qy) = if steep { (y_floor as i32 + 1, x) } else { (x, y_floor as i32 + 1) };
+			fb.line_pixel(px, py, depth, color, alpha * coverage * (1.0 - fraction));
+			fb.line_pixel(qx, qy, depth, color, alpha * coverage * fraction);
+		};
+
+		// Fractional part that stays in [0, 1) for negative values too, unlike `fract`
+		let fpart = |v: f32| v - v.floor();
+
+		// First endpoint
+		let x_end = a.x.round();
+		let y_end = a.y + gradient * (x_end - a.x);
+		let x_gap = 1.0 - fpart(a.x + 0.5);
+		let x_start = x_end as i32;
+		plot(self, x_start, y_end, x_gap);
+		let mut intery = y_end + gradient;
+
+		// Second endpoint
+		let x_end = b.x.round();
+		let y_end = b.y + gradient * (x_end - b.x);
+		let x_gap = fpart(b.x + 0.5);
+		let x_stop = x_end as i32;
+		plot(self, x_stop, y_end, x_gap);
+
+		for x in (x_start + 1)..x_stop {
+			plot(self, x, intery, 1.0);
+			intery += gradient;
+		}
+	}
+}
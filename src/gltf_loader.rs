@@ -0,0 +1,239 @@
+// gltf_loader.rs
+//
+// glTF 2.0 import (.gltf with external or embedded buffers, and .glb). Node
+// transforms are baked into the vertices, so every primitive becomes one
+// part of an `Obj` placed where the file's scene puts it. The hierarchy
+// itself is flattened: parts have no parents, and only their names (the
+// node path, like `Robot/Arm/Hand`) tell where they came from.
+
+use nalgebra_glm::{Mat3, Mat4, Vec2, Vec3, Vec4};
+use std::sync::Arc;
+use crate::color::Color;
+use crate::material::Material;
+use crate::model::ModelError;
+use crate::obj::{Obj, ObjMesh};
+use crate::texture::Texture;
+
+fn invalid(message: &str) -> ModelError {
+    ModelError::Invalid(format!("glTF: {}", message))
+}
+
+pub fn load(filename: &str) -> Result<Obj, ModelError> {
+    let (document, buffers, images) = gltf::import(filename).map_err(ModelError::Gltf)?;
+    convert(&document, &buffers, &images)
+}
+
+/// Builds the `Obj` out of an imported document and its decoded data.
+fn convert(document: &gltf::Document, buffers: &[gltf::buffer::Data], images: &[gltf::image::Data]) -> Result<Obj, ModelError> {
+    let materials: Vec<Arc<Material>> = document
+        .materials()
+        .map(|material| Arc::new(convert_material(&material, images)))
+        .collect();
+
+    let mut meshes = Vec::new();
+    let scene = document.default_scene().or_else(|| document.scenes().next());
+    if let Some(scene) = scene {
+        for node in scene.nodes() {
+            collect_node(&node, &Mat4::identity(), "", buffers, &mut meshes)?;
+        }
+    }
+
+    Ok(Obj::from_meshes(meshes, materials))
+}
+
+/// Adds the primitives of `node` and its children, with `parent` being the
+/// accumulated transform of its ancestors. Parts are named by their node path.
+/// Indices past the end of the positions, or other attributes with a different
+/// count than the positions, make the whole file invalid.
+fn collect_node(node: &gltf::Node, parent: &Mat4, parent_path: &str, buffers: &[gltf::buffer::Data], meshes: &mut Vec<ObjMesh>) -> Result<(), ModelError> {
+    let local: Mat4 = node.transform().matrix().into();
+    let transform = parent * local;
+    let name = node.name().map_or_else(|| format!("node{}", node.index()), str::to_string);
+    let path = if parent_path.is_empty() { name } else { format!("{}/{}", parent_path, name) };
+
+    if let Some(mesh) = node.mesh() {
+        let normal_matrix = Mat3::new(
+            transform[(0, 0)], transform[(0, 1)], transform[(0, 2)],
+            transform[(1, 0)], transform[(1, 1)], transform[(1, 2)],
+            transform[(2, 0)], transform[(2, 1)], transform[(2, 2)],
+        )
+        .try_inverse()
+        .unwrap_or(Mat3::identity())
+        .transpose();
+
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                eprintln!("{}: skipping a {:?} primitive, only triangles are supported", path, primitive.mode());
+                continue;
+            }
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let Some(positions) = reader.read_positions() else {
+                continue;
+            };
+
+            let vertices: Vec<Vec3> = positions
+                .map(|p| (transform * Vec4::new(p[0], p[1], p[2], 1.0)).xyz())
+                .collect();
+            let normals: Vec<Vec3> = reader
+                .read_normals()
+                .map(|normals| normals.map(|n| (normal_matrix * Vec3::new(n[0], n[1], n[2])).normalize()).collect())
+                .unwrap_or_default();
+            // glTF already puts the UV origin at the top left, like our textures
+            let texcoords: Vec<Vec2> = reader
+                .read_tex_coords(0)
+                .map(|uvs| uvs.into_f32().map(|uv| Vec2::new(uv[0], uv[1])).collect())
+                .unwrap_or_default();
+            let colors: Vec<Color> = reader
+                .read_colors(0)
+                .map(|colors| colors.into_rgb_f32().map(|c| Color::new(c[0] * 255.0, c[1] * 255.0, c[2] * 255.0)).collect())
+                .unwrap_or_default();
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..vertices.len() as u32).collect(),
+            };
+
+            if let Some(&index) = indices.iter().find(|&&index| index as usize >= vertices.len()) {
+                return Err(invalid(&format!("{}: index {} is past the {} vertices", path, index, vertices.len())));
+            }
+            for (attribute, count) in [("normals", normals.len()), ("texture coordinates", texcoords.len()), ("colors", colors.len())] {
+                if count != 0 && count != vertices.len() {
+                    return Err(invalid(&format!("{}: {} {} for {} vertices", path, count, attribute, vertices.len())));
+                }
+            }
+
+            meshes.push(ObjMesh {
+                name: path.clone(),
+                vertices,
+                normals,
+                texcoords,
+                colors,
+                indices,
+                material: primitive.material().index(),
+            });
+        }
+    }
+
+    for child in node.children() {
+        collect_node(&child, &transform, &path, buffers, meshes)?;
+    }
+    Ok(())
+}
+
+/// Approximates a metallic-roughness material with our Blinn-Phong one.
+fn convert_material(material: &gltf::Material, images: &[gltf::image::Data]) -> Material {
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, alpha] = pbr.base_color_factor();
+    let base_color = Vec3::new(r, g, b);
+    let metallic = pbr.metallic_factor();
+    // Dielectrics reflect about 4% of the light, metals reflect their own color
+    let specular = Vec3::new(0.04, 0.04, 0.04).lerp(&base_color, metallic);
+    let roughness = pbr.roughness_factor().max(0.05);
+    let [er, eg, eb] = material.emissive_factor();
+
+    let base_texture = pbr.base_color_texture().map(|info| info.texture().source());
+    let diffuse_map = base_texture.as_ref().and_then(|image| match image.source() {
+        gltf::image::Source::Uri { uri, .. } => Some(uri.to_string()),
+        gltf::image::Source::View { .. } => None,
+    });
+    let diffuse_texture = base_texture.and_then(|image| convert_image(&images[image.index()]));
+
+    Material {
+        name: material.name().map_or_else(|| format!("material{}", material.index().unwrap_or(0)), str::to_string),
+        diffuse: base_color,
+        specular,
+        // Common roughness to Phong exponent mapping
+        shininess: 2.0 / (roughness * roughness * roughness * roughness) - 2.0,
+        emissive: Vec3::new(er, eg, eb),
+        dissolve: if material.alpha_mode() == gltf::material::AlphaMode::Blend { alpha } else { 1.0 },
+        diffuse_map,
+        specular_map: None,
+        normal_map: None,
+        diffuse_texture,
+    }
+}
+
+/// Converts decoded image data to a texture. Only 8-bit formats are handled.
+fn convert_image(image: &gltf::image::Data) -> Option<Texture> {
+    use gltf::image::Format;
+    let channels = match image.format {
+        Format::R8 => 1,
+        Format::R8G8 => 2,
+        Format::R8G8B8 => 3,
+        Format::R8G8B8A8 => 4,
+        other => {
+            eprintln!("Skipping a {:?} texture, only 8-bit textures are supported", other);
+            return None;
+        }
+    };
+    let pixels = image
+        .pixels
+        .chunks_exact(channels)
+        .map(|p| match channels {
+            1 | 2 => Color::new(p[0] as f32, p[0] as f32, p[0] as f32),
+            _ => Color::new(p[0] as f32, p[1] as f32, p[2] as f32),
+        })
+        .collect();
+    Some(Texture::from_pixels(image.width as usize, image.height as usize, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three positions, then the u16 indices 0 1 2 and 0 1 5.
+    const BUFFER: &str = "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAABAAUA";
+
+    /// A one-triangle file whose primitive uses `attributes` and `indices`.
+    /// Accessor 0 holds the positions, 1 only two normals, 2 good indices
+    /// and 3 an index past the vertices.
+    fn convert_json(attributes: &str, indices: usize) -> Result<Obj, ModelError> {
+        let json = format!(r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [0] }}],
+            "nodes": [{{ "name": "Triangle", "mesh": 0 }}],
+            "meshes": [{{ "primitives": [{{ "attributes": {}, "indices": {} }}] }}],
+            "buffers": [{{ "byteLength": 48, "uri": "{}" }}],
+            "bufferViews": [
+                {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                {{ "buffer": 0, "byteOffset": 36, "byteLength": 12 }}
+            ],
+            "accessors": [
+                {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] }},
+                {{ "bufferView": 0, "componentType": 5126, "count": 2, "type": "VEC3" }},
+                {{ "bufferView": 1, "byteOffset": 0, "componentType": 5123, "count": 3, "type": "SCALAR" }},
+                {{ "bufferView": 1, "byteOffset": 6, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+            ]
+        }}"#, attributes, indices, BUFFER);
+        let (document, buffers, images) = gltf::import_slice(json.as_bytes()).expect("a valid glTF document");
+        convert(&document, &buffers, &images)
+    }
+
+    fn invalid_message(result: Result<Obj, ModelError>) -> String {
+        match result {
+            Err(ModelError::Invalid(message)) => message,
+            Err(other) => panic!("expected an invalid model, got {}", other),
+            Ok(_) => panic!("expected an invalid model, but it loaded"),
+        }
+    }
+
+    #[test]
+    fn loads_a_valid_triangle() {
+        let obj = convert_json(r#"{ "POSITION": 0 }"#, 2).expect("the triangle loads");
+        assert_eq!(obj.meshes().len(), 1);
+        assert_eq!(obj.meshes()[0].name, "Triangle");
+        assert_eq!(obj.meshes()[0].indices, [0, 1, 2]);
+    }
+
+    #[test]
+    fn rejects_indices_past_the_vertices() {
+        let message = invalid_message(convert_json(r#"{ "POSITION": 0 }"#, 3));
+        assert_eq!(message, "glTF: Triangle: index 5 is past the 3 vertices");
+    }
+
+    #[test]
+    fn rejects_attributes_with_another_count() {
+        let message = invalid_message(convert_json(r#"{ "POSITION": 0, "NORMAL": 1 }"#, 2));
+        assert_eq!(message, "glTF: Triangle: 2 normals for 3 vertices");
+    }
+}
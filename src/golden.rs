@@ -0,0 +1,150 @@
+// golden.rs
+//
+// Golden-image regression checks. Every selectable planet is rendered
+// headlessly from a fixed camera at a fixed time and compared with the
+// reference PNG committed in `tests/golden/`. Small differences, like a
+// rasterization change moving a few edge pixels, are tolerated; anything
+// larger fails and leaves the new render and a diff image in `target/golden/`.
+//
+//     cargo run --release -- --check-golden   # compare (also run by `cargo test`)
+//     cargo run --release -- --bless-golden   # accept the current output
+
+use image::{ImageResult, Rgb, RgbImage};
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::lod::LodState;
+use crate::orbit::Trail;
+use crate::scene::{planet_name, Scene, SELECTABLE_BODIES};
+use crate::shaders::ShaderRegistry;
+use crate::timing::FrameStats;
+use crate::vertex::Vertex;
+use crate::{render_orbits, render_scene, update_trails, TRAIL_LENGTH, TRAIL_SPACING};
+
+pub const REFERENCE_DIR: &str = "tests/golden";
+pub const OUTPUT_DIR: &str = "target/golden";
+const SIZE: usize = 200;
+const SIM_TIME: f32 = 1.0;
+const BACKGROUND_COLOR: u32 = 0x333355;
+
+/// Color distance above which a pixel counts as changed, on the 0-765 scale
+/// of `color_distance`.
+const PIXEL_TOLERANCE: f32 = 12.0;
+/// Fraction of changed pixels a render may have and still pass.
+const CHANGED_FRACTION_TOLERANCE: f32 = 0.002;
+
+/// File name stem of the reference for the planet with this shader number.
+fn case_name(shader: u8) -> String {
+    planet_name(shader).to_lowercase()
+}
+
+fn render_case(shader: u8, meshes: &[Vec<Vertex>]) -> Framebuffer {
+    let scene = Scene::planet_system(shader);
+    let shaders = ShaderRegistry::builtin();
+    let camera = Camera::new(Vec3::new(0.0, 1.2, 3.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    let mut framebuffer = Framebuffer::new(SIZE, SIZE);
+    framebuffer.set_background_color(BACKGROUND_COLOR);
+    framebuffer.clear();
+
+    let mut lod_states = vec![LodState::default(); scene.bodies.len()];
+    let mut trails: Vec<Trail> = scene.bodies.iter().map(|_| Trail::new(TRAIL_LENGTH, TRAIL_SPACING)).collect();
+    render_scene(&mut framebuffer, &scene, meshes, &shaders, &mut lod_states, &camera, SIM_TIME, &mut FrameStats::new(1));
+    update_trails(&mut trails, &scene, SIM_TIME);
+    render_orbits(&mut framebuffer, &scene, &trails, &camera, SIM_TIME, true);
+    framebuffer
+}
+
+fn to_image(framebuffer: &Framebuffer) -> RgbImage {
+    RgbImage::from_raw(framebuffer.width as u32, framebuffer.height as u32, framebuffer.to_rgb_bytes())
+        .expect("the color buffer holds one value per pixel")
+}
+
+/// "Redmean" distance, a cheap approximation of how different two colors
+/// look: green differences count most, and red and blue trade weight
+/// depending on how red the pair is.
+fn color_distance(a: &Rgb<u8>, b: &Rgb<u8>) -> f32 {
+    let mean_red = (a[0] as f32 + b[0] as f32) / 2.0;
+    let [dr, dg, db] = [0, 1, 2].map(|c| a[c] as f32 - b[c] as f32);
+    ((2.0 + mean_red / 256.0) * dr * dr + 4.0 * dg * dg + (2.0 + (255.0 - mean_red) / 256.0) * db * db).sqrt()
+}
+
+struct Comparison {
+    changed: usize,
+    max_distance: f32,
+    /// Changed pixels in red over a dimmed copy of the reference.
+    diff: RgbImage,
+}
+
+fn compare(expected: &RgbImage, actual: &RgbImage) -> Comparison {
+    let mut changed = 0;
+    let mut max_distance: f32 = 0.0;
+    let diff = RgbImage::from_fn(expected.width(), expected.height(), |x, y| {
+        let (e, a) = (expected.get_pixel(x, y), actual.get_pixel(x, y));
+        let distance = color_distance(e, a);
+        max_distance = max_distance.max(distance);
+        if distance > PIXEL_TOLERANCE {
+            changed += 1;
+            Rgb([255, 0, 0])
+        } else {
+            let gray = ((e[0] as u32 + e[1] as u32 + e[2] as u32) / 9) as u8;
+            Rgb([gray, gray, gray])
+        }
+    });
+    Comparison { changed, max_distance, diff }
+}
+
+/// Renders every case and overwrites its reference image.
+pub fn bless(meshes: &[Vec<Vertex>]) -> ImageResult<()> {
+    std::fs::create_dir_all(REFERENCE_DIR)?;
+    for shader in SELECTABLE_BODIES {
+        let filename = format!("{}/{}.png", REFERENCE_DIR, case_name(shader));
+        to_image(&render_case(shader, meshes)).save(&filename)?;
+        println!("Blessed {}", filename);
+    }
+    Ok(())
+}
+
+/// Renders every case and compares it with its reference, printing one line
+/// per case. Returns whether all of them passed.
+pub fn check(meshes: &[Vec<Vertex>]) -> ImageResult<bool> {
+    let mut passed = true;
+    for shader in SELECTABLE_BODIES {
+        let name = case_name(shader);
+        let actual = to_image(&render_case(shader, meshes));
+        let reference = format!("{}/{}.png", REFERENCE_DIR, name);
+        let expected = match image::open(&reference) {
+            Ok(image) => image.to_rgb8(),
+            Err(err) => {
+                println!("FAIL {}: no reference ({}), run with --bless-golden to create it", name, err);
+                passed = false;
+                continue;
+            }
+        };
+
+        if expected.dimensions() != actual.dimensions() {
+            println!("FAIL {}: reference is {:?} but the render is {:?}", name, expected.dimensions(), actual.dimensions());
+            passed = false;
+            continue;
+        }
+
+        let comparison = compare(&expected, &actual);
+        let fraction = comparison.changed as f32 / (SIZE * SIZE) as f32;
+        if fraction <= CHANGED_FRACTION_TOLERANCE {
+            println!("ok   {}: {} pixels changed, max distance {:.1}", name, comparison.changed, comparison.max_distance);
+            continue;
+        }
+
+        passed = false;
+        std::fs::create_dir_all(OUTPUT_DIR)?;
+        let actual_file = format!("{}/{}_actual.png", OUTPUT_DIR, name);
+        let diff_file = format!("{}/{}_diff.png", OUTPUT_DIR, name);
+        actual.save(&actual_file)?;
+        comparison.diff.save(&diff_file)?;
+        println!(
+            "FAIL {}: {} pixels changed ({:.2}%, allowed {:.2}%), max distance {:.1}; see {} and {}",
+            name, comparison.changed, fraction * 100.0, CHANGED_FRACTION_TOLERANCE * 100.0,
+            comparison.max_distance, actual_file, diff_file,
+        );
+    }
+    Ok(passed)
+}
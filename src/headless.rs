@@ -0,0 +1,93 @@
+// headless.rs
+
+use image::ImageResult;
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+use crate::camera_path::CameraPath;
+use crate::framebuffer::Framebuffer;
+use crate::lod::LodState;
+use crate::orbit::Trail;
+use crate::recording::FrameWriter;
+use crate::scene::Scene;
+use crate::shaders::ShaderRegistry;
+use crate::timing::FrameStats;
+use crate::vertex::Vertex;
+use crate::{render_orbits, render_scene, update_trails, TRAIL_LENGTH, TRAIL_SPACING};
+
+/// Saves the framebuffer's color buffer as an 8-bit RGB PNG.
+pub fn save_png(framebuffer: &Framebuffer, filename: &str) -> ImageResult<()> {
+    image::save_buffer(
+        filename,
+        &framebuffer.to_rgb_bytes(),
+        framebuffer.width as u32,
+        framebuffer.height as u32,
+        image::ExtendedColorType::Rgb8,
+    )
+}
+
+/// Plays `path` without a window at `fps`, starting the simulation at
+/// `start_time`, and writes every frame to `target` (see
+/// `FrameWriter::create`). Returns the number of frames written.
+#[allow(clippy::too_many_arguments)]
+pub fn render_camera_path(
+    path: &CameraPath,
+    scene: &Scene,
+    meshes: &[Vec<Vertex>],
+    shaders: &ShaderRegistry,
+    width: usize,
+    height: usize,
+    fps: f32,
+    background_color: u32,
+    start_time: f32,
+    target: &str,
+) -> ImageResult<usize> {
+    let frames = (path.duration() * fps).ceil() as usize + 1;
+    let mut writer = FrameWriter::create(target, width, height, fps)?;
+    let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    record(scene, meshes, shaders, width, height, background_color, &camera, Some(path), start_time, frames, 1.0 / fps, &mut writer)?;
+    writer.finish()?;
+    Ok(frames)
+}
+
+/// Renders `frames` frames without a window, starting at `start_time` and
+/// advancing the simulation by `timestep` seconds between them regardless of
+/// how long rendering takes. The camera follows `path`, timed from the first
+/// frame, when there is one and otherwise stays where `camera` is.
+#[allow(clippy::too_many_arguments)]
+pub fn record(
+    scene: &Scene,
+    meshes: &[Vec<Vertex>],
+    shaders: &ShaderRegistry,
+    width: usize,
+    height: usize,
+    background_color: u32,
+    camera: &Camera,
+    path: Option<&CameraPath>,
+    start_time: f32,
+    frames: usize,
+    timestep: f32,
+    writer: &mut FrameWriter,
+) -> ImageResult<()> {
+    let mut framebuffer = Framebuffer::new(width, height);
+    framebuffer.set_background_color(background_color);
+    let mut camera = camera.clone();
+    let mut trails: Vec<Trail> = scene.bodies.iter().map(|_| Trail::new(TRAIL_LENGTH, TRAIL_SPACING)).collect();
+    let mut lod_states = vec![LodState::default(); scene.bodies.len()];
+    let mut stats = FrameStats::new(1);
+
+    for frame in 0..frames {
+        let elapsed = frame as f32 * timestep;
+        let sim_time = start_time + elapsed;
+        if let Some(path) = path {
+            camera.apply_pose(&path.sample(elapsed));
+        }
+
+        framebuffer.clear();
+        render_scene(&mut framebuffer, scene, meshes, shaders, &mut lod_states, &camera, sim_time, &mut stats);
+        update_trails(&mut trails, scene, sim_time);
+        render_orbits(&mut framebuffer, scene, &trails, &camera, sim_time, true);
+
+        writer.write(&framebuffer)?;
+    }
+    Ok(())
+}
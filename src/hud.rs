@@ -0,0 +1,102 @@
+// hud.rs
+
+use crate::framebuffer::{Framebuffer, TextAlign};
+use crate::input::KeyBindings;
+use crate::timing::{FrameStats, Stage};
+
+const TEXT_COLOR: u32 = 0xFFFFFF;
+const DIM_TEXT_COLOR: u32 = 0xAAAACC;
+const SHADOW_COLOR: u32 = 0x000000;
+const MARGIN: i32 = 8;
+
+/// Everything the HUD shows for the current frame.
+pub struct HudInfo<'a> {
+    pub body_name: &'a str,
+    pub mesh_name: &'a str,
+    pub mesh_triangles: usize,
+    pub sim_time: f32,
+    pub camera_distance: f32,
+    pub camera_mode: &'a str,
+    /// Name of the followed body, when the camera is in follow mode.
+    pub follow_target: Option<&'a str>,
+    pub paused: bool,
+    pub render_size: (usize, usize),
+    pub render_scale: f32,
+    pub upscale_filter: &'a str,
+    pub stats: &'a FrameStats,
+    /// Per-body LOD report, shown above the controls hint when present.
+    pub lod_debug: Option<&'a str>,
+}
+
+/// Text with a one pixel drop shadow so it stays readable over bright planets.
+fn shadowed_text(framebuffer: &mut Framebuffer, x: i32, y: i32, text: &str, color: u32, scale: usize, align: TextAlign) {
+    framebuffer.draw_text(x + 1, y + 1, text, SHADOW_COLOR, scale, align);
+    framebuffer.draw_text(x, y, text, color, scale, align);
+}
+
+/// Draws the overlay on top of the already rendered 3D image.
+pub fn draw_hud(framebuffer: &mut Framebuffer, info: &HudInfo) {
+    let width = framebuffer.width as i32;
+    let height = framebuffer.height as i32;
+
+    shadowed_text(framebuffer, MARGIN, MARGIN, info.body_name, TEXT_COLOR, 2, TextAlign::Left);
+
+    let mut details = format!(
+        "mesh: {} ({} triangles)\nt = {:.1}s\ndistance = {:.2}\ncamera: {}",
+        info.mesh_name,
+        info.mesh_triangles,
+        info.sim_time,
+        info.camera_distance,
+        info.camera_mode,
+    );
+    if let Some(target) = info.follow_target {
+        details.push_str(&format!(" ({})", target));
+    }
+    if info.paused {
+        details.push_str("\npaused");
+    }
+    shadowed_text(framebuffer, MARGIN, MARGIN + 22, &details, DIM_TEXT_COLOR, 1, TextAlign::Left);
+
+    let mut stats = format!(
+        "{:.0} fps\nframe {:.1}ms",
+        info.stats.fps(),
+        info.stats.average_frame_time().as_secs_f32() * 1000.0,
+    );
+    for stage in Stage::ALL {
+        stats.push_str(&format!("\n{} {:.1}ms", stage.name(), info.stats.average(stage).as_secs_f32() * 1000.0));
+    }
+    stats.push_str(&format!(
+        "\nrender {}x{} ({}x, {})",
+        info.render_size.0,
+        info.render_size.1,
+        info.render_scale,
+        info.upscale_filter,
+    ));
+    shadowed_text(framebuffer, width - MARGIN, MARGIN, &stats, DIM_TEXT_COLOR, 1, TextAlign::Right);
+
+    let controls = "F1: key bindings";
+    let (_, controls_height) = Framebuffer::text_size(controls, 1);
+    let controls_y = height - MARGIN - controls_height as i32;
+    shadowed_text(framebuffer, width / 2, controls_y, controls, DIM_TEXT_COLOR, 1, TextAlign::Center);
+
+    if let Some(report) = info.lod_debug {
+        let (_, report_height) = Framebuffer::text_size(report, 1);
+        shadowed_text(framebuffer, MARGIN, controls_y - MARGIN - report_height as i32, report, TEXT_COLOR, 1, TextAlign::Left);
+    }
+}
+
+/// Lists every action with its keys, in two columns.
+pub fn draw_help(framebuffer: &mut Framebuffer, bindings: &KeyBindings) {
+    let lines = bindings.help_lines();
+    let keys_width = lines
+        .iter()
+        .map(|(keys, _)| Framebuffer::text_size(keys, 1).0)
+        .max()
+        .unwrap_or(0) as i32;
+
+    let keys: Vec<&str> = lines.iter().map(|(keys, _)| keys.as_str()).collect();
+    let descriptions: Vec<&str> = lines.iter().map(|(_, description)| description.as_str()).collect();
+    shadowed_text(framebuffer, MARGIN, MARGIN, "Key bindings", TEXT_COLOR, 2, TextAlign::Left);
+    shadowed_text(framebuffer, MARGIN, MARGIN + 24, &keys.join("\n"), TEXT_COLOR, 1, TextAlign::Left);
+    shadowed_text(framebuffer, MARGIN + keys_width + 16, MARGIN + 24, &descriptions.join("\n"), DIM_TEXT_COLOR, 1, TextAlign::Left);
+}
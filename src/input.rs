@@ -0,0 +1,565 @@
+// input.rs
+
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window};
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use std::fmt;
+use crate::camera::{Camera, CameraMode};
+use crate::scene::{planet_name, SELECTABLE_BODIES};
+
+/// Named things the user can do from the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Switch to the planet with this shader number.
+    SelectBody(u8),
+    OrbitLeft,
+    OrbitRight,
+    OrbitUp,
+    OrbitDown,
+    ShiftLeft,
+    ShiftRight,
+    ShiftUp,
+    ShiftDown,
+    ZoomIn,
+    ZoomOut,
+    FlyForward,
+    FlyBackward,
+    FlyLeft,
+    FlyRight,
+    FlyUp,
+    FlyDown,
+    LookLeft,
+    LookRight,
+    LookUp,
+    LookDown,
+    RollLeft,
+    RollRight,
+    CycleCameraMode,
+    CycleFollowTarget,
+    PlayCameraPath,
+    Pause,
+    ToggleHud,
+    ToggleOrbits,
+    ToggleSmoothLines,
+    ToggleHelp,
+    ToggleLodOverlay,
+    RenderScaleUp,
+    RenderScaleDown,
+    CycleUpscaleFilter,
+    CyclePlanetMesh,
+    ExportMesh,
+    Screenshot,
+    ScreenshotWithDepth,
+    Quit,
+}
+
+const ACTIONS: &[Action] = &[
+    Action::OrbitLeft,
+    Action::OrbitRight,
+    Action::OrbitUp,
+    Action::OrbitDown,
+    Action::ShiftLeft,
+    Action::ShiftRight,
+    Action::ShiftUp,
+    Action::ShiftDown,
+    Action::ZoomIn,
+    Action::ZoomOut,
+    Action::FlyForward,
+    Action::FlyBackward,
+    Action::FlyLeft,
+    Action::FlyRight,
+    Action::FlyUp,
+    Action::FlyDown,
+    Action::LookLeft,
+    Action::LookRight,
+    Action::LookUp,
+    Action::LookDown,
+    Action::RollLeft,
+    Action::RollRight,
+    Action::CycleCameraMode,
+    Action::CycleFollowTarget,
+    Action::PlayCameraPath,
+    Action::Pause,
+    Action::ToggleHud,
+    Action::ToggleOrbits,
+    Action::ToggleSmoothLines,
+    Action::ToggleHelp,
+    Action::ToggleLodOverlay,
+    Action::RenderScaleUp,
+    Action::RenderScaleDown,
+    Action::CycleUpscaleFilter,
+    Action::CyclePlanetMesh,
+    Action::ExportMesh,
+    Action::Screenshot,
+    Action::ScreenshotWithDepth,
+    Action::Quit,
+];
+
+impl Action {
+    /// Every action, in the order the help listing shows them.
+    pub fn all() -> Vec<Action> {
+        let mut actions: Vec<Action> = SELECTABLE_BODIES.iter().map(|&body| Action::SelectBody(body)).collect();
+        actions.extend_from_slice(ACTIONS);
+        actions
+    }
+
+    /// Name used in key binding files.
+    pub fn name(&self) -> String {
+        let name = match self {
+            Action::SelectBody(body) => return format!("select-body-{}", body),
+            Action::OrbitLeft => "orbit-left",
+            Action::OrbitRight => "orbit-right",
+            Action::OrbitUp => "orbit-up",
+            Action::OrbitDown => "orbit-down",
+            Action::ShiftLeft => "shift-left",
+            Action::ShiftRight => "shift-right",
+            Action::ShiftUp => "shift-up",
+            Action::ShiftDown => "shift-down",
+            Action::ZoomIn => "zoom-in",
+            Action::ZoomOut => "zoom-out",
+            Action::FlyForward => "fly-forward",
+            Action::FlyBackward => "fly-backward",
+            Action::FlyLeft => "fly-left",
+            Action::FlyRight => "fly-right",
+            Action::FlyUp => "fly-up",
+            Action::FlyDown => "fly-down",
+            Action::LookLeft => "look-left",
+            Action::LookRight => "look-right",
+            Action::LookUp => "look-up",
+            Action::LookDown => "look-down",
+            Action::RollLeft => "roll-left",
+            Action::RollRight => "roll-right",
+            Action::CycleCameraMode => "camera-mode",
+            Action::CycleFollowTarget => "follow-next",
+            Action::PlayCameraPath => "play-camera-path",
+            Action::Pause => "pause",
+            Action::ToggleHud => "toggle-hud",
+            Action::ToggleOrbits => "toggle-orbits",
+            Action::ToggleSmoothLines => "toggle-smooth-lines",
+            Action::ToggleHelp => "help",
+            Action::ToggleLodOverlay => "lod-overlay",
+            Action::RenderScaleUp => "render-scale-up",
+            Action::RenderScaleDown => "render-scale-down",
+            Action::CycleUpscaleFilter => "upscale-filter",
+            Action::CyclePlanetMesh => "planet-mesh",
+            Action::ExportMesh => "export-mesh",
+            Action::Screenshot => "screenshot",
+            Action::ScreenshotWithDepth => "screenshot-depth",
+            Action::Quit => "quit",
+        };
+        name.to_string()
+    }
+
+    pub fn description(&self) -> String {
+        let description = match self {
+            Action::SelectBody(body) => return format!("Show {}", planet_name(*body)),
+            Action::OrbitLeft => "Orbit camera left",
+            Action::OrbitRight => "Orbit camera right",
+            Action::OrbitUp => "Orbit camera up",
+            Action::OrbitDown => "Orbit camera down",
+            Action::ShiftLeft => "Turn view center left",
+            Action::ShiftRight => "Turn view center right",
+            Action::ShiftUp => "Turn view center up",
+            Action::ShiftDown => "Turn view center down",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::FlyForward => "Free-fly: move forward",
+            Action::FlyBackward => "Free-fly: move backward",
+            Action::FlyLeft => "Free-fly: strafe left",
+            Action::FlyRight => "Free-fly: strafe right",
+            Action::FlyUp => "Free-fly: move up",
+            Action::FlyDown => "Free-fly: move down",
+            Action::LookLeft => "Free-fly: look left",
+            Action::LookRight => "Free-fly: look right",
+            Action::LookUp => "Free-fly: look up",
+            Action::LookDown => "Free-fly: look down",
+            Action::RollLeft => "Free-fly: roll left",
+            Action::RollRight => "Free-fly: roll right",
+            Action::CycleCameraMode => "Cycle camera mode",
+            Action::CycleFollowTarget => "Follow next body",
+            Action::PlayCameraPath => "Play or stop the camera path",
+            Action::Pause => "Pause or resume the simulation",
+            Action::ToggleHud => "Show or hide the HUD",
+            Action::ToggleOrbits => "Show or hide orbits and trails",
+            Action::ToggleSmoothLines => "Toggle anti-aliased lines",
+            Action::ToggleHelp => "Show or hide this help",
+            Action::ToggleLodOverlay => "Show or hide the level of detail of each body",
+            Action::RenderScaleUp => "Render at a higher resolution",
+            Action::RenderScaleDown => "Render at a lower resolution",
+            Action::CycleUpscaleFilter => "Cycle the upscaling filter",
+            Action::CyclePlanetMesh => "Cycle the planet mesh (OBJ or generated)",
+            Action::ExportMesh => "Export the planet mesh to OBJ and PLY",
+            Action::Screenshot => "Save a screenshot",
+            Action::ScreenshotWithDepth => "Save a screenshot and its depth buffer",
+            Action::Quit => "Quit",
+        };
+        description.to_string()
+    }
+
+    fn default_keys(&self) -> Vec<Key> {
+        match self {
+            Action::SelectBody(body) => {
+                let index = *body as usize;
+                vec![NUMPAD_DIGITS[index], DIGITS[index]]
+            }
+            Action::OrbitLeft => vec![Key::Left],
+            Action::OrbitRight => vec![Key::Right],
+            Action::OrbitUp => vec![Key::W],
+            Action::OrbitDown => vec![Key::S],
+            Action::ShiftLeft => vec![Key::A],
+            Action::ShiftRight => vec![Key::D],
+            Action::ShiftUp => vec![Key::Q],
+            Action::ShiftDown => vec![Key::E],
+            Action::ZoomIn => vec![Key::Up],
+            Action::ZoomOut => vec![Key::Down],
+            Action::FlyForward => vec![Key::W],
+            Action::FlyBackward => vec![Key::S],
+            Action::FlyLeft => vec![Key::A],
+            Action::FlyRight => vec![Key::D],
+            Action::FlyUp => vec![Key::E],
+            Action::FlyDown => vec![Key::Q],
+            Action::LookLeft => vec![Key::Left],
+            Action::LookRight => vec![Key::Right],
+            Action::LookUp => vec![Key::Up],
+            Action::LookDown => vec![Key::Down],
+            Action::RollLeft => vec![Key::Z],
+            Action::RollRight => vec![Key::V],
+            Action::CycleCameraMode => vec![Key::C],
+            Action::CycleFollowTarget => vec![Key::Tab],
+            Action::PlayCameraPath => vec![Key::P],
+            Action::Pause => vec![Key::Space],
+            Action::ToggleHud => vec![Key::H],
+            Action::ToggleOrbits => vec![Key::O],
+            Action::ToggleSmoothLines => vec![Key::L],
+            Action::ToggleHelp => vec![Key::F1],
+            Action::ToggleLodOverlay => vec![Key::F2],
+            Action::RenderScaleUp => vec![Key::Equal, Key::NumPadPlus],
+            Action::RenderScaleDown => vec![Key::Minus, Key::NumPadMinus],
+            Action::CycleUpscaleFilter => vec![Key::F],
+            Action::CyclePlanetMesh => vec![Key::M],
+            Action::ExportMesh => vec![Key::X],
+            Action::Screenshot => vec![Key::F12],
+            Action::ScreenshotWithDepth => vec![Key::F11],
+            Action::Quit => vec![Key::Escape],
+        }
+    }
+}
+
+const DIGITS: [Key; 10] = [
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4,
+    Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+];
+
+const NUMPAD_DIGITS: [Key; 10] = [
+    Key::NumPad0, Key::NumPad1, Key::NumPad2, Key::NumPad3, Key::NumPad4,
+    Key::NumPad5, Key::NumPad6, Key::NumPad7, Key::NumPad8, Key::NumPad9,
+];
+
+/// Keys that can be named in a binding file. Names are the minifb variant names.
+const BINDABLE_KEYS: &[Key] = &[
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10,
+    Key::F11, Key::F12, Key::F13, Key::F14, Key::F15,
+    Key::Down, Key::Left, Key::Right, Key::Up,
+    Key::Apostrophe, Key::Backquote, Key::Backslash, Key::Comma, Key::Equal, Key::LeftBracket,
+    Key::Minus, Key::Period, Key::RightBracket, Key::Semicolon, Key::Slash,
+    Key::Backspace, Key::Delete, Key::End, Key::Enter, Key::Escape, Key::Home, Key::Insert, Key::Menu,
+    Key::PageDown, Key::PageUp, Key::Pause, Key::Space, Key::Tab,
+    Key::NumLock, Key::CapsLock, Key::ScrollLock,
+    Key::LeftShift, Key::RightShift, Key::LeftCtrl, Key::RightCtrl,
+    Key::NumPad0, Key::NumPad1, Key::NumPad2, Key::NumPad3, Key::NumPad4,
+    Key::NumPad5, Key::NumPad6, Key::NumPad7, Key::NumPad8, Key::NumPad9,
+    Key::NumPadDot, Key::NumPadSlash, Key::NumPadAsterisk, Key::NumPadMinus, Key::NumPadPlus, Key::NumPadEnter,
+    Key::LeftAlt, Key::RightAlt, Key::LeftSuper, Key::RightSuper,
+];
+
+pub fn key_name(key: Key) -> String {
+    format!("{:?}", key)
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    BINDABLE_KEYS.iter().copied().find(|key| key_name(*key).eq_ignore_ascii_case(name))
+}
+
+#[derive(Debug)]
+pub enum KeyBindingsError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    UnknownAction(String),
+    UnknownKey { action: String, key: String },
+    InvalidValue(String),
+}
+
+impl fmt::Display for KeyBindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyBindingsError::Io(err) => write!(f, "could not read key bindings: {}", err),
+            KeyBindingsError::Parse(err) => write!(f, "invalid key bindings file: {}", err),
+            KeyBindingsError::UnknownAction(action) => write!(f, "unknown action '{}'", action),
+            KeyBindingsError::UnknownKey { action, key } => write!(f, "unknown key '{}' for action '{}'", key, action),
+            KeyBindingsError::InvalidValue(action) => {
+                write!(f, "action '{}' must be a key name or a list of key names", action)
+            }
+        }
+    }
+}
+
+/// Keys bound to each action. Files only need to list the actions they change:
+///
+/// ```toml
+/// select-body-1 = ["NumPad1", "Key1"]
+/// pause = "Pause"
+/// ```
+pub struct KeyBindings {
+    bindings: Vec<(Action, Vec<Key>)>,
+}
+
+impl KeyBindings {
+    pub fn defaults() -> Self {
+        KeyBindings {
+            bindings: Action::all().into_iter().map(|action| (action, action.default_keys())).collect(),
+        }
+    }
+
+    pub fn load(filename: &str) -> Result<Self, KeyBindingsError> {
+        let text = std::fs::read_to_string(filename).map_err(KeyBindingsError::Io)?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, KeyBindingsError> {
+        let table: toml::Table = toml::from_str(text).map_err(KeyBindingsError::Parse)?;
+        let mut bindings = Self::defaults();
+
+        for (name, value) in table {
+            let names: Vec<&str> = match &value {
+                toml::Value::String(key) => vec![key.as_str()],
+                toml::Value::Array(keys) => keys
+                    .iter()
+                    .map(|key| key.as_str().ok_or_else(|| KeyBindingsError::InvalidValue(name.clone())))
+                    .collect::<Result<_, _>>()?,
+                _ => return Err(KeyBindingsError::InvalidValue(name)),
+            };
+            let keys = names
+                .iter()
+                .map(|key| parse_key(key).ok_or_else(|| KeyBindingsError::UnknownKey {
+                    action: name.clone(),
+                    key: key.to_string(),
+                }))
+                .collect::<Result<Vec<Key>, _>>()?;
+
+            let entry = bindings
+                .bindings
+                .iter_mut()
+                .find(|(action, _)| action.name() == name)
+                .ok_or_else(|| KeyBindingsError::UnknownAction(name.clone()))?;
+            entry.1 = keys;
+        }
+
+        Ok(bindings)
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    /// Whether any key bound to `action` is held down.
+    pub fn is_down(&self, window: &Window, action: Action) -> bool {
+        self.keys(action).iter().any(|key| window.is_key_down(*key))
+    }
+
+    /// Whether any key bound to `action` was pressed this frame, ignoring key repeat.
+    pub fn is_pressed(&self, window: &Window, action: Action) -> bool {
+        self.keys(action).iter().any(|key| window.is_key_pressed(*key, KeyRepeat::No))
+    }
+
+    /// One `(keys, description)` pair per action, for on-screen help.
+    pub fn help_lines(&self) -> Vec<(String, String)> {
+        self.bindings
+            .iter()
+            .map(|(action, keys)| {
+                let names: Vec<String> = keys.iter().map(|key| key_name(*key)).collect();
+                (names.join(", "), action.description())
+            })
+            .collect()
+    }
+
+    /// The bindings as a commented key bindings file, usable as a starting point
+    /// for a custom one.
+    pub fn help_text(&self) -> String {
+        let mut text = String::new();
+        for (action, keys) in &self.bindings {
+            let names: Vec<String> = keys.iter().map(|key| format!("\"{}\"", key_name(*key))).collect();
+            text.push_str(&format!("# {}\n{} = [{}]\n", action.description(), action.name(), names.join(", ")));
+        }
+        text
+    }
+
+    /// Keyboard camera controls for the current camera mode, applied once per frame.
+    pub fn move_camera(&self, window: &Window, camera: &mut Camera) {
+        if camera.mode == CameraMode::FreeFly {
+            self.fly_camera(window, camera);
+            return;
+        }
+
+        let movement_speed = 1.0;
+        let rotation_speed = PI / 50.0;
+        let zoom_speed = 0.1;
+
+        if self.is_down(window, Action::OrbitLeft) {
+            camera.orbit(rotation_speed, 0.0);
+        }
+        if self.is_down(window, Action::OrbitRight) {
+            camera.orbit(-rotation_speed, 0.0);
+        }
+        if self.is_down(window, Action::OrbitUp) {
+            camera.orbit(0.0, -rotation_speed);
+        }
+        if self.is_down(window, Action::OrbitDown) {
+            camera.orbit(0.0, rotation_speed);
+        }
+
+        let mut movement = Vec3::new(0.0, 0.0, 0.0);
+        if self.is_down(window, Action::ShiftLeft) {
+            movement.x -= movement_speed;
+        }
+        if self.is_down(window, Action::ShiftRight) {
+            movement.x += movement_speed;
+        }
+        if self.is_down(window, Action::ShiftUp) {
+            movement.y += movement_speed;
+        }
+        if self.is_down(window, Action::ShiftDown) {
+            movement.y -= movement_speed;
+        }
+        if movement.magnitude() > 0.0 {
+            camera.move_center(movement);
+        }
+
+        if self.is_down(window, Action::ZoomIn) {
+            camera.zoom(zoom_speed);
+        }
+        if self.is_down(window, Action::ZoomOut) {
+            camera.zoom(-zoom_speed);
+        }
+    }
+
+    /// Free-fly keys: move along the camera's own axes, look around and roll.
+    fn fly_camera(&self, window: &Window, camera: &mut Camera) {
+        let movement_speed = 0.05;
+        let look_speed = PI / 100.0;
+
+        let mut movement = Vec3::new(0.0, 0.0, 0.0);
+        if self.is_down(window, Action::FlyForward) {
+            movement.z += movement_speed;
+        }
+        if self.is_down(window, Action::FlyBackward) {
+            movement.z -= movement_speed;
+        }
+        if self.is_down(window, Action::FlyLeft) {
+            movement.x -= movement_speed;
+        }
+        if self.is_down(window, Action::FlyRight) {
+            movement.x += movement_speed;
+        }
+        if self.is_down(window, Action::FlyUp) {
+            movement.y += movement_speed;
+        }
+        if self.is_down(window, Action::FlyDown) {
+            movement.y -= movement_speed;
+        }
+        if movement.magnitude() > 0.0 {
+            camera.fly(movement);
+        }
+
+        if self.is_down(window, Action::LookLeft) {
+            camera.look(-look_speed, 0.0);
+        }
+        if self.is_down(window, Action::LookRight) {
+            camera.look(look_speed, 0.0);
+        }
+        if self.is_down(window, Action::LookUp) {
+            camera.look(0.0, look_speed);
+        }
+        if self.is_down(window, Action::LookDown) {
+            camera.look(0.0, -look_speed);
+        }
+        if self.is_down(window, Action::RollLeft) {
+            camera.roll(-look_speed);
+        }
+        if self.is_down(window, Action::RollRight) {
+            camera.roll(look_speed);
+        }
+    }
+}
+
+/// Mouse camera controls: left-drag orbits (or looks around in free-fly mode),
+/// the wheel zooms and right- or middle-drag pans.
+pub struct MouseInput {
+    last_position: Option<(f32, f32)>,
+    /// Radians of rotation per pixel dragged.
+    pub orbit_sensitivity: f32,
+    /// Fraction of the camera distance moved per pixel dragged while panning.
+    pub pan_sensitivity: f32,
+    /// Exponent applied per wheel unit; each notch scales the distance by exp(-zoom_rate * delta).
+    pub zoom_rate: f32,
+}
+
+impl MouseInput {
+    pub fn new() -> Self {
+        MouseInput {
+            last_position: None,
+            orbit_sensitivity: 0.01,
+            pan_sensitivity: 0.002,
+            zoom_rate: 0.1,
+        }
+    }
+
+    pub fn update(&mut self, window: &Window, camera: &mut Camera) {
+        let position = window.get_mouse_pos(MouseMode::Clamp);
+        let left = window.get_mouse_down(MouseButton::Left);
+        let pan = window.get_mouse_down(MouseButton::Right) || window.get_mouse_down(MouseButton::Middle);
+
+        if let (Some((x, y)), Some((last_x, last_y))) = (position, self.last_position) {
+            let dx = x - last_x;
+            let dy = y - last_y;
+            if dx != 0.0 || dy != 0.0 {
+                if left {
+                    // Grab-style: dragging right swings the camera left around the center
+                    match camera.mode {
+                        CameraMode::FreeFly => camera.look(dx * self.orbit_sensitivity, -dy * self.orbit_sensitivity),
+                        _ => camera.orbit(dx * self.orbit_sensitivity, -dy * self.orbit_sensitivity),
+                    }
+                } else if pan {
+                    let distance = (camera.eye - camera.center).magnitude();
+                    let scale = distance * self.pan_sensitivity;
+                    camera.pan(-dx * scale, dy * scale);
+                }
+            }
+        }
+        self.last_position = if left || pan { position } else { None };
+
+        if let Some((_, scroll)) = window.get_scroll_wheel() {
+            if scroll != 0.0 {
+                let factor = (-scroll * self.zoom_rate).exp();
+                match camera.mode {
+                    CameraMode::FreeFly => {
+                        let distance = (camera.eye - camera.center).magnitude();
+                        camera.fly(Vec3::new(0.0, 0.0, distance * (1.0 - factor)));
+                    }
+                    _ => camera.scale_distance(factor),
+                }
+            }
+        }
+    }
+}
+
+impl Default for MouseInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
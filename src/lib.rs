@@ -0,0 +1,397 @@
+// lib.rs
+//
+// The software renderer behind the planet viewer: mesh loading, scenes,
+// cameras, the rasterizer and its shaders. The windowed app in main.rs is
+// one user of it; tools can build a `Scene`, render it into a `Framebuffer`
+// with `render_scene` and save or inspect the result.
+
+use nalgebra_glm::{Vec3, Mat4, look_at, mat4_to_mat3, perspective};
+use std::path::Path;
+use std::time::Instant;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+pub mod framebuffer;
+mod triangle;
+pub mod vertex;
+pub mod obj;
+pub mod color;
+pub mod fragment;
+pub mod shaders;
+pub mod camera;
+pub mod timing;
+mod font;
+pub mod hud;
+pub mod orbit;
+pub mod scene;
+pub mod camera_path;
+pub mod headless;
+pub mod input;
+pub mod upscale;
+pub mod mesh;
+pub mod lod;
+pub mod texture;
+pub mod material;
+pub mod normals;
+pub mod gltf_loader;
+pub mod model;
+pub mod ply;
+pub mod stl;
+pub mod mesh_cache;
+pub mod terrain;
+pub mod export;
+pub mod screenshot;
+pub mod recording;
+pub mod golden;
+pub mod scene_file;
+pub mod scene_state;
+
+pub use framebuffer::Framebuffer;
+pub use vertex::Vertex;
+pub use obj::Obj;
+pub use shaders::{FragmentShader, ShaderRegistry};
+pub use camera::Camera;
+pub use timing::FrameStats;
+pub use scene::{Body, Light, Scene};
+
+use triangle::triangle;
+use shaders::vertex_shader;
+use timing::Stage;
+use orbit::{Trail, draw_path, draw_trail};
+use lod::{LodState, screen_radius, select_level};
+use material::Material;
+use model::ModelError;
+use scene::ModelPart;
+use normals::NormalMode;
+use terrain::Displacement;
+
+// Shader time advances in ticks; the original loop ran one tick per ~16ms frame.
+pub const TICKS_PER_SECOND: f32 = 60.0;
+pub const ORBIT_SEGMENTS: usize = 128;
+pub const TRAIL_LENGTH: usize = 90;
+pub const TRAIL_SPACING: f32 = 0.02;
+
+/// Everything the vertex and fragment shaders read besides the vertex itself.
+pub struct Uniforms {
+    pub model_matrix: Mat4,
+    pub view_matrix: Mat4,
+    pub projection_matrix: Mat4,
+    pub viewport_matrix: Mat4,
+    pub time: u32,
+    /// Material of the body being drawn, if it has one.
+    pub material: Option<Arc<Material>>,
+    /// Terrain relief applied by the vertex shader.
+    pub displacement: Option<Displacement>,
+    /// Lights with their directions in the object space of the body being
+    /// drawn, where the normals are.
+    pub lights: Vec<Light>,
+    /// Camera eye in the same object space, for view-dependent shading.
+    pub camera_position: Vec3,
+}
+
+/// Object-to-world transform: rotate about X, then Y, then Z, then scale and translate.
+pub fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
+    let (sin_x, cos_x) = rotation.x.sin_cos();
+    let (sin_y, cos_y) = rotation.y.sin_cos();
+    let (sin_z, cos_z) = rotation.z.sin_cos();
+
+    let rotation_matrix_x = Mat4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.0, cos_x, -sin_x, 0.0,
+        0.0, sin_x, cos_x, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    );
+
+    let rotation_matrix_y = Mat4::new(
+        cos_y, 0.0, sin_y, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        -sin_y, 0.0, cos_y, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    );
+
+    let rotation_matrix_z = Mat4::new(
+        cos_z, -sin_z, 0.0, 0.0,
+        sin_z, cos_z, 0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    );
+
+    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
+
+    let transform_matrix = Mat4::new(
+        scale, 0.0, 0.0, translation.x,
+        0.0, scale, 0.0, translation.y,
+        0.0, 0.0, scale, translation.z,
+        0.0, 0.0, 0.0, 1.0,
+    );
+    
+    transform_matrix * rotation_matrix
+}
+
+/// World-to-camera transform looking from `eye` at `center`.
+pub fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
+    look_at(&eye, &center, &up)
+}
+
+/// Perspective projection for a viewport of the given size, with the vertical field of view in degrees.
+pub fn create_perspective_matrix(window_width: f32, window_height: f32, fov_degrees: f32) -> Mat4 {
+    let fov = fov_degrees * PI / 180.0;
+    let aspect_ratio = window_width / window_height;
+    let near = 0.1;
+    let far = 1000.0;
+
+    perspective(fov, aspect_ratio, near, far)
+}
+
+/// Maps normalized device coordinates to pixels, with Y pointing down.
+pub fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
+    Mat4::new(
+        width / 2.0, 0.0, 0.0, width / 2.0,
+        0.0, -height / 2.0, 0.0, height / 2.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0
+    )
+}
+
+/// Draws `vertex_array`, three vertices per triangle, into `framebuffer`,
+/// coloring every fragment with `shader`.
+pub fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], shader: FragmentShader, stats: &mut FrameStats){
+    let start = Instant::now();
+    let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
+    for vertex in vertex_array {
+        let transformed = vertex_shader(vertex, uniforms);
+        transformed_vertices.push(transformed);
+    }
+    stats.record(Stage::Vertex, start.elapsed());
+
+    let start = Instant::now();
+    let mut triangles = Vec::new();
+    for i in (0..transformed_vertices.len()).step_by(3) {
+        if i+2 < transformed_vertices.len() {
+            triangles.push([
+                transformed_vertices[i].clone(),
+                transformed_vertices[i+1].clone(),
+                transformed_vertices[i+2].clone(),
+            ]);
+        }
+    }
+
+    let mut fragments = Vec::new();
+    for tri in &triangles {
+        fragments.extend(triangle(&tri[0], &tri[1], &tri[2], &uniforms.lights));
+    }
+    stats.record(Stage::Raster, start.elapsed());
+
+    let start = Instant::now();
+    let dissolve = uniforms.material.as_ref().map_or(1.0, |material| material.dissolve);
+    for fragment in fragments {
+        let x = fragment.position.x as usize;
+        let y = fragment.position.y as usize;
+        if x < framebuffer.width && y < framebuffer.height {
+            //let shaded_color = fragment_shader_saturn_with_ring(&fragment, &uniforms);
+            let shaded_color = shader(&fragment, uniforms);
+            let color = shaded_color.to_hex();
+            framebuffer.set_current_color(color);
+            if dissolve < 1.0 {
+                framebuffer.blend_point(x, y, fragment.depth, dissolve);
+            } else {
+                framebuffer.point(x , y, fragment.depth);
+            }
+        }
+    }
+    stats.record(Stage::Shade, start.elapsed());
+}
+
+/// Uniforms for one draw call of a frame rendered into `framebuffer` from `camera`.
+pub fn frame_uniforms(framebuffer: &Framebuffer, camera: &Camera, model_matrix: Mat4, sim_time: f32) -> Uniforms {
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+    Uniforms {
+        model_matrix,
+        view_matrix: create_view_matrix(camera.eye, camera.center, camera.up),
+        projection_matrix: create_perspective_matrix(width, height, camera.fov),
+        viewport_matrix: create_viewport_matrix(width, height),
+        time: (sim_time * TICKS_PER_SECOND) as u32,
+        material: None,
+        displacement: None,
+        lights: vec![Light::default()],
+        camera_position: (model_matrix.try_inverse().unwrap_or(Mat4::identity()) * camera.eye.push(1.0)).xyz(),
+    }
+}
+
+/// Draws every body of `scene` as seen from `camera` at `sim_time`, each with
+/// the level of detail that fits its size on screen. `lod_states` holds one
+/// entry per body and is updated with the levels used.
+#[allow(clippy::too_many_arguments)]
+pub fn render_scene(framebuffer: &mut Framebuffer, scene: &Scene, meshes: &[Vec<Vertex>], shaders: &ShaderRegistry, lod_states: &mut [LodState], camera: &Camera, sim_time: f32, stats: &mut FrameStats) {
+    // Translucent materials don't write depth, so they go after everything opaque
+    let mut order: Vec<usize> = (0..scene.bodies.len()).collect();
+    order.sort_by_key(|&index| scene.bodies[index].material.as_ref().is_some_and(|material| material.dissolve < 1.0));
+
+    for index in order {
+        let body = &scene.bodies[index];
+        let lod = &mut lod_states[index];
+        let translation = scene.world_position(index, sim_time);
+        let model_matrix = create_model_matrix(translation, body.scale, body.rotation_at(sim_time));
+        let mut uniforms = frame_uniforms(framebuffer, camera, model_matrix, sim_time);
+        uniforms.material = body.material.clone();
+        uniforms.displacement = body.displacement;
+        // Normals stay in object space, so the lights are turned into the body's frame instead
+        let rotation = mat4_to_mat3(&create_model_matrix(Vec3::zeros(), 1.0, body.rotation_at(sim_time))).transpose();
+        uniforms.lights = scene.lights.iter()
+            .map(|light| Light { direction: rotation * light.direction, ..*light })
+            .collect();
+
+        if !body.lods.is_empty() {
+            let radius = lod.mesh_radius(meshes, body.mesh) * body.scale;
+            lod.screen_radius = screen_radius(&translation, radius, &uniforms);
+            lod.level = select_level(&body.lods, lod.level, lod.screen_radius);
+        } else {
+            *lod = LodState::default();
+        }
+        render(framebuffer, &uniforms, &meshes[body.mesh_for_level(lod.level)], shaders.for_body(body), stats);
+    }
+}
+
+/// One line per body with the LOD it is drawn with, for the debug overlay.
+pub fn lod_report(scene: &Scene, meshes: &[Vec<Vertex>], lod_states: &[LodState]) -> String {
+    let lines: Vec<String> = scene.bodies.iter().zip(lod_states).map(|(body, lod)| {
+        let triangles = meshes[body.mesh_for_level(lod.level)].len() / 3;
+        if body.lods.is_empty() {
+            format!("{}: full ({} tris)", body.name, triangles)
+        } else {
+            format!("{}: LOD {} ({:.0}px, {} tris)", body.name, lod.level, lod.screen_radius, triangles)
+        }
+    }).collect();
+    lines.join("\n")
+}
+
+/// Records the current position of every orbiting body in its trail.
+pub fn update_trails(trails: &mut [Trail], scene: &Scene, sim_time: f32) {
+    for (index, trail) in trails.iter_mut().enumerate() {
+        if scene.bodies[index].orbit.is_some() {
+            trail.push(scene.world_position(index, sim_time));
+        }
+    }
+}
+
+/// Draws orbit ellipses and trails on top of the rendered bodies.
+pub fn render_orbits(framebuffer: &mut Framebuffer, scene: &Scene, trails: &[Trail], camera: &Camera, sim_time: f32, antialiased: bool) {
+    // Paths are already in world space, so the model matrix is the identity
+    let uniforms = frame_uniforms(framebuffer, camera, Mat4::identity(), sim_time);
+    for (index, trail) in trails.iter().enumerate() {
+        if let Some(path) = scene.orbit_path(index, sim_time, ORBIT_SEGMENTS) {
+            draw_path(framebuffer, &uniforms, &path, 0x8888CC, 0.6, antialiased);
+            draw_trail(framebuffer, &uniforms, trail, 0xFFEEAA, antialiased);
+        }
+    }
+}
+
+/// Prints where an OBJ has triangles with no area, listing the first few.
+pub fn warn_degenerate_triangles(filename: &str, obj: &Obj) {
+    let degenerate = obj.degenerate_triangles();
+    if degenerate.is_empty() {
+        return;
+    }
+    let listed: Vec<String> = degenerate
+        .iter()
+        .take(5)
+        .map(|(mesh, triangle)| format!("mesh {} triangle {}", mesh, triangle))
+        .collect();
+    let more = if degenerate.len() > listed.len() { ", ..." } else { "" };
+    eprintln!("{}: {} degenerate triangles ({}{})", filename, degenerate.len(), listed.join(", "), more);
+}
+
+/// Loads an OBJ's vertex array, or builds one with `fallback` when the file can't be read.
+pub fn load_mesh_or(filename: &str, normals: NormalMode, fallback: impl FnOnce() -> Vec<Vertex>) -> Vec<Vertex> {
+    match mesh_cache::load_obj(filename) {
+        Ok(mut obj) => {
+            obj.generate_normals(normals);
+            warn_degenerate_triangles(filename, &obj);
+            obj.get_vertex_array()
+        }
+        Err(err) => {
+            eprintln!("Could not load {} ({}), using a generated mesh instead", filename, err);
+            fallback()
+        }
+    }
+}
+
+/// Loads a model file (OBJ, glTF, PLY or STL), adding each of its parts to
+/// `meshes` and printing what it contains. `file.obj#Part` keeps only the
+/// part called `Part`.
+pub fn load_model(arg: &str, normals: NormalMode, meshes: &mut Vec<Vec<Vertex>>) -> Result<Vec<ModelPart>, ModelError> {
+    let (filename, part_name) = match arg.split_once('#') {
+        Some((filename, part)) => (filename, Some(part)),
+        None => (arg, None),
+    };
+    let mut obj = model::load(filename)?;
+    obj.generate_normals(normals);
+    warn_degenerate_triangles(filename, &obj);
+    for material in obj.materials() {
+        let maps: Vec<&str> = [&material.diffuse_map, &material.specular_map, &material.normal_map]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if maps.is_empty() {
+            println!("{}: material {}", filename, material.name);
+        } else {
+            println!("{}: material {} ({})", filename, material.name, maps.join(", "));
+        }
+    }
+
+    let selected: Vec<usize> = match part_name {
+        Some(name) => match obj.find_part(name) {
+            Some(index) => vec![index],
+            None => {
+                let names = obj.parts().into_iter().map(|part| part.name).collect();
+                return Err(ModelError::MissingPart(name.to_string(), names));
+            }
+        },
+        None => (0..obj.parts().len()).collect(),
+    };
+
+    let stem = Path::new(filename).file_stem().map_or(filename.to_string(), |stem| stem.to_string_lossy().into_owned());
+    let parts = obj.parts();
+    Ok(selected.into_iter().map(|index| {
+        let part = &parts[index];
+        println!(
+            "{}: part {} with {} vertices, center {:.2?}, size {:.2?}",
+            filename, part.name, part.vertex_range.len(), part.bounds.center().as_slice(), part.bounds.size().as_slice(),
+        );
+        // Models split by usemtl repeat their name, so tell them apart by material
+        let repeated = parts.iter().filter(|other| other.name == part.name).count() > 1;
+        let name = match (&part.material, part.name.is_empty()) {
+            (_, true) => stem.clone(),
+            (Some(material), false) if repeated => format!("{} ({})", part.name, material.name),
+            _ => part.name.clone(),
+        };
+        meshes.push(obj.get_part_vertex_array(index));
+        ModelPart { name, mesh: meshes.len() - 1, material: part.material.clone() }
+    }).collect())
+}
+
+/// The meshes every scene can use, indexed by the scene::*_MESH constants,
+/// with the OBJ files read from the working directory.
+pub fn standard_meshes(normals: NormalMode) -> Vec<Vec<Vertex>> {
+    standard_meshes_in(Path::new("."), normals)
+}
+
+/// Like `standard_meshes`, reading `sphere.obj` and `ring.obj` from `asset_dir`.
+pub fn standard_meshes_in(asset_dir: &Path, normals: NormalMode) -> Vec<Vec<Vertex>> {
+    let asset = |name: &str| asset_dir.join(name).to_string_lossy().into_owned();
+    // The OBJ files are optional; generated meshes of the same size stand in for them
+    let vertex_arrays = load_mesh_or(&asset("sphere.obj"), normals, || mesh::uv_sphere(0.5, 32, 16));
+    let ring_vertex_array = load_mesh_or(&asset("ring.obj"), normals, || mesh::annulus(1.2, 2.0, 64));
+
+    vec![
+        vertex_arrays,
+        ring_vertex_array,
+        mesh::uv_sphere(0.5, 48, 24),
+        mesh::icosphere(0.5, 3),
+        mesh::torus(0.35, 0.15, 48, 24),
+        mesh::uv_sphere(0.5, 16, 8),
+        mesh::uv_sphere(0.5, 8, 4),
+    ]
+}
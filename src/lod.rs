@@ -0,0 +1,76 @@
+// lod.rs
+
+use nalgebra_glm::{Vec3, Vec4};
+use crate::vertex::Vertex;
+use crate::Uniforms;
+
+/// Fraction of a threshold the screen radius has to move past before the level
+/// changes, so a body sitting right at a threshold doesn't flicker between meshes.
+const HYSTERESIS: f32 = 0.15;
+
+/// A coarser mesh a body switches to when it gets small on screen.
+#[derive(Clone, Debug)]
+pub struct Lod {
+    pub mesh: usize,
+    /// Projected radius in pixels below which this level is used.
+    pub max_screen_radius: f32,
+}
+
+/// Level chosen for a body in the last frame, kept between frames for hysteresis.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LodState {
+    /// 0 is the body's own mesh, `n` is its `n`th entry in `lods`.
+    pub level: usize,
+    pub screen_radius: f32,
+    /// Index and bounding radius of the body's mesh, measured the first time
+    /// it is drawn and again only when the body switches meshes.
+    pub mesh_radius: Option<(usize, f32)>,
+}
+
+impl LodState {
+    /// Bounding radius of `meshes[mesh]`, from the cache when it is that mesh's.
+    pub fn mesh_radius(&mut self, meshes: &[Vec<Vertex>], mesh: usize) -> f32 {
+        match self.mesh_radius {
+            Some((cached, radius)) if cached == mesh => radius,
+            _ => {
+                let radius = bounding_radius(&meshes[mesh]);
+                self.mesh_radius = Some((mesh, radius));
+                radius
+            }
+        }
+    }
+}
+
+/// Radius of the sphere around the model origin that contains every vertex.
+pub fn bounding_radius(vertices: &[Vertex]) -> f32 {
+    vertices.iter().map(|v| v.position.magnitude()).fold(0.0, f32::max)
+}
+
+/// Approximate radius in pixels of a world-space sphere once projected.
+/// Spheres that reach behind the camera count as infinitely large.
+pub fn screen_radius(center: &Vec3, radius: f32, uniforms: &Uniforms) -> f32 {
+    let view = uniforms.view_matrix * Vec4::new(center.x, center.y, center.z, 1.0);
+    let depth = -view.z;
+    if depth <= radius {
+        return f32::INFINITY;
+    }
+    // projection[(1, 1)] is cot(fov / 2); the viewport maps NDC y to half the height
+    let pixels_per_unit = uniforms.projection_matrix[(1, 1)] * uniforms.viewport_matrix[(1, 1)].abs();
+    radius * pixels_per_unit / depth
+}
+
+/// Picks the level for `screen_radius`, starting from the `current` one.
+/// Levels are ordered from finest to coarsest and only step past a threshold
+/// once the radius is clear of it by the hysteresis margin.
+pub fn select_level(lods: &[Lod], current: usize, screen_radius: f32) -> usize {
+    let mut level = current.min(lods.len());
+    // Coarser while the radius is well below the next level's threshold
+    while level < lods.len() && screen_radius < lods[level].max_screen_radius * (1.0 - HYSTERESIS) {
+        level += 1;
+    }
+    // Finer while the radius is well above the current level's threshold
+    while level > 0 && screen_radius > lods[level - 1].max_screen_radius * (1.0 + HYSTERESIS) {
+        level -= 1;
+    }
+    level
+}
@@ -1,309 +1,324 @@
-use nalgebra_glm::{Vec3, Mat4, look_at, perspective};
-use minifb::{Key, Window, WindowOptions};
-use std::time::Duration;
-use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use minifb::{Window, WindowOptions};
+use std::path::Path;
+use std::time::Instant;
+use clap::Parser;
+
+mod cli;
+use cli::Args;
+
+use shaders_lab4::{export, golden, headless, recording, scene_file, screenshot};
+use shaders_lab4::framebuffer::Framebuffer;
+use shaders_lab4::shaders::ShaderRegistry;
+use shaders_lab4::camera::{Camera, CameraMode};
+use shaders_lab4::timing::{FrameLimiter, FrameStats, Stage};
+use shaders_lab4::hud::{HudInfo, draw_help, draw_hud};
+use shaders_lab4::scene::PLANET_MESHES;
+use shaders_lab4::scene_state::SceneState;
+use shaders_lab4::camera_path::CameraPath;
+use shaders_lab4::input::{Action, KeyBindings, MouseInput};
+use shaders_lab4::upscale::{Filter, RENDER_SCALES, resample};
+use shaders_lab4::normals::NormalMode;
+use shaders_lab4::{load_model, standard_meshes_in};
+
+const DEFAULT_KEYBINDINGS_FILE: &str = "keybindings.toml";
 
-mod framebuffer;
-mod triangle;
-mod vertex;
-mod obj;
-mod color;
-mod fragment;
-mod shaders;
-mod camera;
-
-use framebuffer::Framebuffer;
-use vertex::Vertex;
-use obj::Obj;
-use triangle::triangle;
-use shaders::{vertex_shader, fragment_shader_urano, fragment_shader_neptune, fragment_shader_jupiter, fragment_shader_saturn_with_ring, fragment_shader_venus, fragment_shader_mars, fragment_shader_earth, fragment_shader_mercury, fragment_shader_sun, fragment_shader_moon, fragment_shader_ring};
-use camera::Camera;
-
-pub struct Uniforms {
-    model_matrix: Mat4,
-    view_matrix: Mat4,
-    projection_matrix: Mat4,
-    viewport_matrix: Mat4,
-    time: u32,
-}
-
-fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
-    let (sin_x, cos_x) = rotation.x.sin_cos();
-    let (sin_y, cos_y) = rotation.y.sin_cos();
-    let (sin_z, cos_z) = rotation.z.sin_cos();
-
-    let rotation_matrix_x = Mat4::new(
-        1.0, 0.0, 0.0, 0.0,
-        0.0, cos_x, -sin_x, 0.0,
-        0.0, sin_x, cos_x, 0.0,
-        0.0, 0.0, 0.0, 1.0,
-    );
-
-    let rotation_matrix_y = Mat4::new(
-        cos_y, 0.0, sin_y, 0.0,
-        0.0, 1.0, 0.0, 0.0,
-        -sin_y, 0.0, cos_y, 0.0,
-        0.0, 0.0, 0.0, 1.0,
-    );
-
-    let rotation_matrix_z = Mat4::new(
-        cos_z, -sin_z, 0.0, 0.0,
-        sin_z, cos_z, 0.0, 0.0,
-        0.0, 0.0, 1.0, 0.0,
-        0.0, 0.0, 0.0, 1.0,
-    );
-
-    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
-
-    let transform_matrix = Mat4::new(
-        scale, 0.0, 0.0, translation.x,
-        0.0, scale, 0.0, translation.y,
-        0.0, 0.0, scale, translation.z,
-        0.0, 0.0, 0.0, 1.0,
-    );
-    
-    transform_matrix * rotation_matrix
-}
-
-fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
-    look_at(&eye, &center, &up)
-}
-
-fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
-    let fov = 45.0 * PI / 180.0;
-    let aspect_ratio = window_width / window_height;
-    let near = 0.1;
-    let far = 1000.0;
-
-    perspective(fov, aspect_ratio, near, far)
-}
-
-fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
-    Mat4::new(
-        width / 2.0, 0.0, 0.0, width / 2.0,
-        0.0, -height / 2.0, 0.0, height / 2.0,
-        0.0, 0.0, 1.0, 0.0,
-        0.0, 0.0, 0.0, 1.0
-    )
-}
-
-fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], shader_type: u8){
-    let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
-    for vertex in vertex_array {
-        let transformed = vertex_shader(vertex, uniforms);
-        transformed_vertices.push(transformed);
+fn main() {
+    let args = Args::parse();
+    let window_width = args.width as usize;
+    let window_height = args.height as usize;
+    // Index into RENDER_SCALES; the framebuffer is the window size times this scale
+    let mut render_scale_index = RENDER_SCALES.iter().position(|&scale| scale == 1.0).unwrap();
+    let mut upscale_filter = Filter::Bilinear;
+    let target_fps = 60;
+    let stats_window = 60;
+    let default_background_color = 0x333355;
+
+    if args.list_keys {
+        print!("{}", KeyBindings::defaults().help_text());
+        return;
     }
-
-    let mut triangles = Vec::new();
-    for i in (0..transformed_vertices.len()).step_by(3) {
-        if i+2 < transformed_vertices.len() {
-            triangles.push([
-                transformed_vertices[i].clone(),
-                transformed_vertices[i+1].clone(),
-                transformed_vertices[i+2].clone(),
-            ]);
+    let bindings = match &args.keybindings {
+        Some(filename) => KeyBindings::load(filename),
+        None if Path::new(DEFAULT_KEYBINDINGS_FILE).exists() => KeyBindings::load(DEFAULT_KEYBINDINGS_FILE),
+        None => Ok(KeyBindings::defaults()),
+    }
+    .unwrap_or_else(|err| {
+        eprintln!("{}: {}", args.keybindings.as_deref().unwrap_or(DEFAULT_KEYBINDINGS_FILE), err);
+        std::process::exit(1);
+    });
+
+    let camera_path = args.camera_path.as_ref().map(|filename| {
+        CameraPath::load(filename).unwrap_or_else(|err| {
+            eprintln!("{}: {}", filename, err);
+            std::process::exit(1);
+        })
+    });
+
+    let normals = args.normals;
+
+    // Golden images always use the meshes as they come, whatever --normals says
+    if args.bless_golden {
+        if let Err(err) = golden::bless(&standard_meshes_in(&args.assets, NormalMode::FromFile)) {
+            eprintln!("Could not write the golden images: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.check_golden {
+        match golden::check(&standard_meshes_in(&args.assets, NormalMode::FromFile)) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(err) => {
+                eprintln!("Golden image check failed: {}", err);
+                std::process::exit(1);
+            }
         }
     }
 
-    let mut fragments = Vec::new();
-    for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2]));
+    let mut meshes = standard_meshes_in(&args.assets, normals);
+    let shaders = ShaderRegistry::builtin();
+
+    let mut sim_time = args.time;
+    // A model given with --model replaces the planet, keeping its own materials
+    let model = args.model.as_ref().map(|arg| {
+        load_model(arg, normals, &mut meshes).unwrap_or_else(|err| {
+            eprintln!("{}: {}", arg.split('#').next().unwrap_or(arg), err);
+            std::process::exit(1);
+        })
+    });
+    // A scene file replaces the planet systems altogether, so the number keys don't switch it
+    let scene_file = args.scene.as_ref().map(|filename| {
+        scene_file::load(filename, &mut meshes, &shaders, normals).unwrap_or_else(|err| {
+            eprintln!("{}: {}", filename, err);
+            std::process::exit(1);
+        })
+    });
+    let (mut state, file_camera, file_background) = match scene_file {
+        Some(file) => (SceneState::fixed(file.scene), file.camera, file.background),
+        None => (SceneState::planets(args.body, model), None, None),
+    };
+    let background_color = args.background.or(file_background).unwrap_or(default_background_color);
+
+    // Command-line options override the scene file's camera, which overrides the default view
+    let mut camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    if let Some(pose) = &file_camera {
+        camera.apply_pose(pose);
+    }
+    camera.eye = args.eye.unwrap_or(camera.eye);
+    camera.center = args.target.unwrap_or(camera.center);
+    camera.up = args.up.unwrap_or(camera.up);
+    camera.fov = args.fov.unwrap_or(camera.fov);
+
+    if let Some(filename) = &args.export_mesh {
+        let vertices = export::body_mesh(&state.scene, &meshes, &shaders, 0, sim_time, args.bake_colors);
+        match export::write_mesh(filename, &vertices, args.bake_colors) {
+            Ok(count) => println!("Wrote {} ({} vertices, {} triangles)", filename, count, vertices.len() / 3),
+            Err(err) => {
+                eprintln!("{}: {}", filename, err);
+                std::process::exit(1);
+            }
+        }
+        return;
     }
 
-    for fragment in fragments {
-        let x = fragment.position.x as usize;
-        let y = fragment.position.y as usize;
-        if x < framebuffer.width && y < framebuffer.height {
-            //let shaded_color = fragment_shader_saturn_with_ring(&fragment, &uniforms);
-            let shaded_color = match shader_type {
-                1 => fragment_shader_jupiter(&fragment, uniforms),
-                2 => fragment_shader_saturn_with_ring(&fragment, uniforms),
-                3 => fragment_shader_urano(&fragment, uniforms),
-                4 => fragment_shader_venus(&fragment, uniforms),
-                5 => fragment_shader_mars(&fragment, uniforms),
-                6 => fragment_shader_earth(&fragment, uniforms),
-                7 => fragment_shader_mercury(&fragment, uniforms),
-                8 => fragment_shader_sun(&fragment, uniforms),
-                9 => fragment_shader_moon(&fragment, uniforms),
-                10 => fragment_shader_ring(&fragment, uniforms),
-                _ => fragment_shader_neptune(&fragment, uniforms),
-            };
-            let color = shaded_color.to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x , y, fragment.depth);
+    if let Some(target) = &args.record {
+        let frames = args.frames as usize;
+        let timestep = args.timestep.unwrap_or(1.0 / args.fps);
+        let result = recording::FrameWriter::create(target, window_width, window_height, args.fps).and_then(|mut writer| {
+            headless::record(&state.scene, &meshes, &shaders, window_width, window_height, background_color, &camera, camera_path.as_ref(), sim_time, frames, timestep, &mut writer)?;
+            writer.finish()
+        });
+        match result {
+            Ok(()) => println!("Recorded {} frames, {} s apart, to {}", frames, timestep, target),
+            Err(err) => {
+                eprintln!("Recording failed: {}", err);
+                std::process::exit(1);
+            }
         }
+        return;
     }
-}
 
-fn main() {
-    let window_width = 600;
-    let window_height = 600;
-    let framebuffer_width = 600;
-    let framebuffer_height = 600;
-    let frame_delay = Duration::from_millis(16);
+    if let Some(out_dir) = &args.headless {
+        // clap makes --headless require --camera-path
+        let path = camera_path.as_ref().expect("--headless without a camera path");
+        match headless::render_camera_path(path, &state.scene, &meshes, &shaders, window_width, window_height, args.fps, background_color, sim_time, out_dir) {
+            Ok(frames) => println!("Wrote {} frames to {}", frames, out_dir),
+            Err(err) => {
+                eprintln!("Headless render failed: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    let mut framebuffer = Framebuffer::new(window_width, window_height);
+    // Window-sized image the framebuffer is resampled into, with the HUD drawn on top
+    let mut present = Framebuffer::new(window_width, window_height);
     let mut window = Window::new(
         "Rust 3D model",
         window_width,
         window_height,
-        WindowOptions::default(),
+        WindowOptions {
+            resize: true,
+            ..WindowOptions::default()
+        },
     )
     .unwrap();
 
     window.set_position(0,0);
+    // Pacing is done by FrameLimiter so the present timing isn't inflated by minifb's own sleep
+    window.set_target_fps(0);
     window.update();
 
-    framebuffer.set_background_color(0x333355);
-
-    let mut translation = Vec3::new(0.0, 0.0, 0.0);
-    let mut rotation = Vec3::new(0.0,0.0,0.0);
-    let mut scale = 1.0f32;
-
-    let mut camera = Camera::new(
-        Vec3::new(0.0, 0.0, 5.0),
-        Vec3::new(0.0, 0.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0)
-    );
-
-    let obj = Obj::load("./sphere.obj").expect("Failed to load obj");
-    let vertex_arrays = obj.get_vertex_array();
-
-    let moon_obj = Obj::load("./moon.obj").expect("Failed to load moon.obj");
-    let moon_vertex_array = moon_obj.get_vertex_array();
-
-    let ring_obj = Obj::load("./ring.obj").expect("Failed to load ring.obj");
-    let ring_vertex_array = ring_obj.get_vertex_array();
-
-    let mut time = 0;
-    let mut shader_type = 0;
-
-    let projection_matrix = create_perspective_matrix(window_width as f32, window_height as f32);
-    let viewport_matrix = create_viewport_matrix(framebuffer_width as f32, framebuffer_height as f32);
+    framebuffer.set_background_color(background_color);
+
+    let mut limiter = FrameLimiter::new(target_fps);
+    let mut stats = FrameStats::new(stats_window);
+    let mut last_title_update = Instant::now();
+    let mut show_hud = true;
+    let mut show_orbits = true;
+    let mut antialiased_lines = true;
+    let mut follow_target = 0;
+    // Simulation time at which camera path playback started
+    let mut path_start: Option<f32> = None;
+    let mut mouse = MouseInput::new();
+    let mut paused = false;
+    let mut show_help = false;
+    let mut show_lod = false;
+    // Index into PLANET_MESHES
+    let mut planet_mesh = 0;
 
     while window.is_open() {
-        if window.is_key_down(Key::Escape) {
+        if bindings.is_down(&window, Action::Quit) {
             break;
         }
         
-        time += 1;
-
-        if window.is_key_down(Key::NumPad1) { shader_type = 1; } 
-        if window.is_key_down(Key::NumPad2) { shader_type = 2; } 
-        if window.is_key_down(Key::NumPad3) { shader_type = 3; } 
-        if window.is_key_down(Key::NumPad4) { shader_type = 4; } 
-        if window.is_key_down(Key::NumPad5) { shader_type = 5; } 
-        if window.is_key_down(Key::NumPad6) { shader_type = 6; }
-        if window.is_key_down(Key::NumPad7) { shader_type = 7; }
-        if window.is_key_down(Key::NumPad8) { shader_type = 8; }
-        if window.is_key_down(Key::NumPad0) { shader_type = 0; }
-
-        framebuffer.clear();
-
-        handle_input(&window, &mut camera);
-
-        let model_matrix = create_model_matrix(translation, scale, rotation);
-        let view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
-        let uniforms= Uniforms { 
-            model_matrix, 
-            view_matrix, 
-            projection_matrix, 
-            viewport_matrix,
-            time,
-        };
-
-        framebuffer.set_current_color(0xFFDDDD);
-        //render(&mut framebuffer, &uniforms, &vertex_arrays, shader_type);
-        if shader_type == 2 {
-            // Render Saturn
-            render(&mut framebuffer, &uniforms, &vertex_arrays, shader_type);
-
-            // Adjust ring's transformation matrix
-            let ring_translation = Vec3::new(0.0, 0.0, 0.0); // Centered on Saturn
-            let ring_scale = 0.6; // Adjust scale to fit around Saturn
-            let ring_rotation = Vec3::new(0.0, rotation.y, 0.0); // Rotate with Saturn
-            let ring_model_matrix = create_model_matrix(ring_translation, ring_scale, ring_rotation);
-
-            let ring_uniforms = Uniforms {
-                model_matrix: ring_model_matrix,
-                view_matrix,
-                projection_matrix,
-                viewport_matrix,
-                time,
-            };
+        stats.begin_frame();
+
+        // Projection and viewport are rebuilt from the framebuffer size every
+        // frame, so keeping it proportional to the window keeps the aspect ratio correct
+        let (width, height) = window.get_size();
+        if width > 0 && height > 0 {
+            let scale = RENDER_SCALES[render_scale_index];
+            let render_width = ((width as f32 * scale).round() as usize).max(1);
+            let render_height = ((height as f32 * scale).round() as usize).max(1);
+            if (render_width, render_height) != (framebuffer.width, framebuffer.height) {
+                framebuffer.resize(render_width, render_height);
+            }
+            if (width, height) != (present.width, present.height) {
+                present.resize(width, height);
+            }
+        }
 
-            render(&mut framebuffer, &ring_uniforms, &ring_vertex_array, 10); // Use shader 10 for the ring
+        // Whether to save a screenshot of this frame, and with depth or not
+        let mut screenshot = None;
+        for action in Action::all() {
+            if !bindings.is_pressed(&window, action) {
+                continue;
+            }
+            match action {
+                // Switching planets brings new bodies, so following starts over
+                Action::SelectBody(body) if state.select_body(body) => follow_target = 0,
+                Action::ToggleHud => show_hud = !show_hud,
+                Action::ToggleOrbits => show_orbits = !show_orbits,
+                Action::ToggleSmoothLines => antialiased_lines = !antialiased_lines,
+                Action::ToggleHelp => show_help = !show_help,
+                Action::ToggleLodOverlay => show_lod = !show_lod,
+                Action::Pause => paused = !paused,
+                Action::RenderScaleUp => render_scale_index = (render_scale_index + 1).min(RENDER_SCALES.len() - 1),
+                Action::RenderScaleDown => render_scale_index = render_scale_index.saturating_sub(1),
+                Action::CycleUpscaleFilter => upscale_filter = upscale_filter.next(),
+                Action::CyclePlanetMesh => planet_mesh = (planet_mesh + 1) % PLANET_MESHES.len(),
+                Action::ExportMesh => export::export_body(&state.scene, &meshes, &shaders, 0, sim_time),
+                Action::Screenshot => screenshot = Some(false),
+                Action::ScreenshotWithDepth => screenshot = Some(true),
+                Action::CycleCameraMode => {
+                    camera.mode = camera.mode.next();
+                    if camera.mode != CameraMode::FreeFly {
+                        camera.level();
+                    }
+                }
+                Action::CycleFollowTarget => follow_target += 1,
+                Action::PlayCameraPath if camera_path.is_some() => {
+                    path_start = if path_start.is_some() { None } else { Some(sim_time) };
+                }
+                _ => {}
+            }
+        }
 
-        } else if shader_type == 6 {
-            render(&mut framebuffer, &uniforms, &vertex_arrays, shader_type); // Render Earth
-            // Calculate moon's orbital angle based on time for circular motion
-            let orbit_radius = 1.0;
-            let angle = (uniforms.time as f32) * 0.05;//djust speed by modifying the multiplier
+        follow_target %= state.scene.bodies.len();
+        state.set_planet_mesh(PLANET_MESHES[planet_mesh].0);
 
-            // Position the moon in orbit around Earth
-            let moon_translation = Vec3::new(orbit_radius * angle.cos(), 0.0, orbit_radius * angle.sin());
-            let moon_rotation = Vec3::new(0.0, angle, 0.0); // Rotate the moon to face outward in orbit
-            let moon_model_matrix = create_model_matrix(moon_translation, 0.3, moon_rotation);
+        framebuffer.clear();
 
-            let moon_uniforms = Uniforms {
-                model_matrix: moon_model_matrix,
-                view_matrix,
-                projection_matrix,
-                viewport_matrix,
-                time,
-            };
-            render(&mut framebuffer, &moon_uniforms, &vertex_arrays, 9); // Render Moon with shader 9 (example)
-        } else {
-            render(&mut framebuffer, &uniforms, &vertex_arrays, shader_type);
+        match (&camera_path, path_start) {
+            (Some(path), Some(start)) => {
+                let elapsed = sim_time - start;
+                camera.apply_pose(&path.sample(elapsed));
+                if !path.looping && elapsed > path.duration() {
+                    path_start = None;
+                }
+            }
+            _ => {
+                bindings.move_camera(&window, &mut camera);
+                mouse.update(&window, &mut camera);
+                if camera.mode == CameraMode::Follow {
+                    camera.follow(state.scene.world_position(follow_target, sim_time));
+                }
+            }
         }
 
-        window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-            .unwrap();
-
-        std::thread::sleep(frame_delay);
-    }
-}
+        state.render(&mut framebuffer, &meshes, &shaders, &camera, sim_time, &mut stats);
+        if show_orbits {
+            state.render_orbits(&mut framebuffer, &camera, sim_time, antialiased_lines);
+        }
+        if let Some(with_depth) = screenshot {
+            if let Err(err) = screenshot::take(&framebuffer, with_depth) {
+                eprintln!("Could not save screenshot: {}", err);
+            }
+        }
 
-fn handle_input(window: &Window, camera: &mut Camera){
-    let movement_speed= 1.0;
-    let rotation_speed = PI/50.0;
-    let zoom_speed = 0.1;
+        stats.time(Stage::Present, || {
+            resample(&framebuffer, &mut present, upscale_filter);
+        });
+
+        if show_help {
+            draw_help(&mut present, &bindings);
+        } else if show_hud {
+            let lod_debug = show_lod.then(|| state.lod_report(&meshes));
+            draw_hud(&mut present, &HudInfo {
+                body_name: &state.scene.bodies[0].name,
+                mesh_name: PLANET_MESHES[planet_mesh].1,
+                mesh_triangles: meshes[state.scene.bodies[0].mesh_for_level(state.lod_states[0].level)].len() / 3,
+                sim_time,
+                camera_distance: (camera.eye - camera.center).magnitude(),
+                camera_mode: camera.mode.name(),
+                follow_target: (camera.mode == CameraMode::Follow).then(|| state.scene.bodies[follow_target].name.as_str()),
+                paused,
+                render_size: (framebuffer.width, framebuffer.height),
+                render_scale: RENDER_SCALES[render_scale_index],
+                upscale_filter: upscale_filter.name(),
+                stats: &stats,
+                lod_debug: lod_debug.as_deref(),
+            });
+        }
 
-    if window.is_key_down(Key::Left){
-        camera.orbit(rotation_speed, 0.0);
-    }
-    if window.is_key_down(Key::Right){
-        camera.orbit(-rotation_speed, 0.0);
-    }
-    if window.is_key_down(Key::W){
-        camera.orbit(0.0, -rotation_speed);
-    }
-    if window.is_key_down(Key::S){
-        camera.orbit(0.0, rotation_speed);
-    }
+        stats.time(Stage::Present, || {
+            window
+                .update_with_buffer(&present.buffer, present.width, present.height)
+                .unwrap();
+        });
+        stats.end_frame();
 
-    let mut movement = Vec3::new(0.0, 0.0, 0.0);
-    if window.is_key_down(Key::A){
-        movement.x -= movement_speed;
-    }
-    if window.is_key_down(Key::D){
-        movement.x += movement_speed;
-    }
-    if window.is_key_down(Key::Q){
-        movement.y += movement_speed;
-    }
-    if window.is_key_down(Key::E){
-        movement.y -= movement_speed;
-    }
-    if movement.magnitude() > 0.0 {
-        camera.move_center(movement);
-    }
+        if last_title_update.elapsed().as_secs_f32() >= 1.0 {
+            window.set_title(&format!("Rust 3D model - {}", stats.summary()));
+            last_title_update = Instant::now();
+        }
 
-    if window.is_key_down(Key::Up){
-        camera.zoom(zoom_speed);
-    }
-    if window.is_key_down(Key::Down){
-        camera.zoom(-zoom_speed);
+        let delta = limiter.wait().as_secs_f32();
+        if !paused {
+            sim_time += delta;
+        }
     }
 }
@@ -0,0 +1,90 @@
+// material.rs
+
+use nalgebra_glm::Vec3;
+use std::path::Path;
+use crate::texture::Texture;
+
+/// Surface description read from an MTL file. Colors are in the 0-1 range.
+pub struct Material {
+    pub name: String,
+    /// `Kd`
+    pub diffuse: Vec3,
+    /// `Ks`
+    pub specular: Vec3,
+    /// `Ns`, the specular exponent.
+    pub shininess: f32,
+    /// `Ke`, light the surface gives off regardless of lighting.
+    pub emissive: Vec3,
+    /// `d`, where 1 is opaque.
+    pub dissolve: f32,
+    /// `map_Kd`, `map_Ks` and `map_Bump`, as written in the MTL file.
+    pub diffuse_map: Option<String>,
+    pub specular_map: Option<String>,
+    pub normal_map: Option<String>,
+    /// The diffuse map, when it could be loaded.
+    pub diffuse_texture: Option<Texture>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            name: String::new(),
+            diffuse: Vec3::new(0.8, 0.8, 0.8),
+            specular: Vec3::new(0.0, 0.0, 0.0),
+            shininess: 1.0,
+            emissive: Vec3::new(0.0, 0.0, 0.0),
+            dissolve: 1.0,
+            diffuse_map: None,
+            specular_map: None,
+            normal_map: None,
+            diffuse_texture: None,
+        }
+    }
+}
+
+fn vec3(v: [f32; 3]) -> Vec3 {
+    Vec3::new(v[0], v[1], v[2])
+}
+
+/// Parses a parameter tobj doesn't know about, such as `Ke 1.0 0.5 0.0`.
+fn parse_vec3(text: &str) -> Option<Vec3> {
+    let values: Vec<f32> = text.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+    match values[..] {
+        [r, g, b] => Some(Vec3::new(r, g, b)),
+        [gray] => Some(Vec3::new(gray, gray, gray)),
+        _ => None,
+    }
+}
+
+impl Material {
+    /// Converts a material parsed by tobj. Texture paths are relative to
+    /// `base_dir`, the directory of the OBJ file.
+    pub fn from_mtl(material: tobj::Material, base_dir: &Path) -> Self {
+        let defaults = Material::default();
+        let mut converted = Material {
+            diffuse: material.diffuse.map(vec3).unwrap_or(defaults.diffuse),
+            specular: material.specular.map(vec3).unwrap_or(defaults.specular),
+            shininess: material.shininess.unwrap_or(defaults.shininess),
+            emissive: material.unknown_param.get("Ke").and_then(|ke| parse_vec3(ke)).unwrap_or(defaults.emissive),
+            dissolve: material.dissolve.unwrap_or(defaults.dissolve),
+            diffuse_map: material.diffuse_texture,
+            specular_map: material.specular_texture,
+            normal_map: material.normal_texture,
+            diffuse_texture: None,
+            name: material.name,
+        };
+        converted.load_diffuse_texture(base_dir);
+        converted
+    }
+
+    /// Loads `diffuse_map`, relative to `base_dir`, into `diffuse_texture`.
+    /// A texture that can't be read is reported and left out.
+    pub fn load_diffuse_texture(&mut self, base_dir: &Path) {
+        self.diffuse_texture = self.diffuse_map.as_ref().and_then(|map| {
+            let path = base_dir.join(map);
+            Texture::load(&path.to_string_lossy())
+                .map_err(|err| eprintln!("Could not load texture {}: {}", path.display(), err))
+                .ok()
+        });
+    }
+}
@@ -0,0 +1,194 @@
+// mesh.rs
+//
+// Procedural meshes. Every generator returns a flat triangle list in the same
+// layout as `Obj::get_vertex_array`, counter-clockwise when seen from outside.
+
+use nalgebra_glm::{Vec2, Vec3};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use crate::vertex::Vertex;
+
+/// Indexed mesh used while generating, expanded to a vertex array at the end.
+struct IndexedMesh {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    indices: Vec<u32>,
+}
+
+impl IndexedMesh {
+    fn new() -> Self {
+        IndexedMesh {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    fn push_vertex(&mut self, position: Vec3, normal: Vec3, uv: Vec2) -> u32 {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.uvs.push(uv);
+        (self.positions.len() - 1) as u32
+    }
+
+    /// Two triangles for the quad a-b-c-d, given in counter-clockwise order.
+    fn push_quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.indices.extend_from_slice(&[a, b, c, a, c, d]);
+    }
+
+    fn into_vertex_array(self) -> Vec<Vertex> {
+        self.indices
+            .iter()
+            .map(|&i| {
+                let i = i as usize;
+                Vertex::new(self.positions[i], self.normals[i], self.uvs[i])
+            })
+            .collect()
+    }
+}
+
+/// Latitude/longitude sphere. `segments` splits it around the Y axis and
+/// `rings` from pole to pole. The texture seam is duplicated so UVs don't wrap.
+pub fn uv_sphere(radius: f32, segments: usize, rings: usize) -> Vec<Vertex> {
+    let segments = segments.max(3);
+    let rings = rings.max(2);
+    let mut mesh = IndexedMesh::new();
+
+    for ring in 0..=rings {
+        let v = ring as f32 / rings as f32;
+        let theta = v * PI;
+        for segment in 0..=segments {
+            let u = segment as f32 / segments as f32;
+            let phi = u * 2.0 * PI;
+            let normal = Vec3::new(theta.sin() * phi.cos(), theta.cos(), -theta.sin() * phi.sin());
+            mesh.push_vertex(normal * radius, normal, Vec2::new(u, v));
+        }
+    }
+
+    let row = (segments + 1) as u32;
+    for ring in 0..rings as u32 {
+        for segment in 0..segments as u32 {
+            let top_left = ring * row + segment;
+            let bottom_left = top_left + row;
+            mesh.push_quad(top_left, bottom_left, bottom_left + 1, top_left + 1);
+        }
+    }
+
+    mesh.into_vertex_array()
+}
+
+/// Spherical UV for a unit direction, matching the mapping used by `uv_sphere`.
+fn spherical_uv(direction: &Vec3) -> Vec2 {
+    let u = (-direction.z).atan2(direction.x) / (2.0 * PI);
+    let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+    Vec2::new(if u < 0.0 { u + 1.0 } else { u }, v)
+}
+
+/// Icosahedron subdivided `subdivisions` times, with every vertex pushed onto
+/// the sphere. Triangles are much more even than a UV sphere's, at the cost
+/// of a visible seam in the UV mapping.
+pub fn icosphere(radius: f32, subdivisions: u32) -> Vec<Vertex> {
+    let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let mut directions: Vec<Vec3> = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| Vec3::new(x, y, z).normalize())
+    .collect();
+
+    let mut faces: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // Shared edges must reuse the same midpoint or the surface cracks
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32, directions: &mut Vec<Vec3>| -> u32 {
+            let key = (a.min(b), a.max(b));
+            *midpoints.entry(key).or_insert_with(|| {
+                let middle = (directions[a as usize] + directions[b as usize]).normalize();
+                directions.push(middle);
+                (directions.len() - 1) as u32
+            })
+        };
+
+        let mut subdivided = Vec::with_capacity(faces.len() * 4);
+        for [a, b, c] in faces {
+            let ab = midpoint(a, b, &mut directions);
+            let bc = midpoint(b, c, &mut directions);
+            let ca = midpoint(c, a, &mut directions);
+            subdivided.extend_from_slice(&[[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+        }
+        faces = subdivided;
+    }
+
+    faces
+        .iter()
+        .flat_map(|face| face.iter())
+        .map(|&i| {
+            let direction = directions[i as usize];
+            Vertex::new(direction * radius, direction, spherical_uv(&direction))
+        })
+        .collect()
+}
+
+/// Torus around the Y axis. `segments` splits it around the Y axis and `sides`
+/// around the tube.
+pub fn torus(major_radius: f32, minor_radius: f32, segments: usize, sides: usize) -> Vec<Vertex> {
+    let segments = segments.max(3);
+    let sides = sides.max(3);
+    let mut mesh = IndexedMesh::new();
+
+    for segment in 0..=segments {
+        let u = segment as f32 / segments as f32;
+        let phi = u * 2.0 * PI;
+        let ring_center = Vec3::new(phi.cos(), 0.0, -phi.sin()) * major_radius;
+        for side in 0..=sides {
+            let v = side as f32 / sides as f32;
+            let theta = v * 2.0 * PI;
+            let normal = Vec3::new(theta.cos() * phi.cos(), theta.sin(), -theta.cos() * phi.sin());
+            mesh.push_vertex(ring_center + normal * minor_radius, normal, Vec2::new(u, v));
+        }
+    }
+
+    let row = (sides + 1) as u32;
+    for segment in 0..segments as u32 {
+        for side in 0..sides as u32 {
+            let a = segment * row + side;
+            let b = a + row;
+            mesh.push_quad(a, b, b + 1, a + 1);
+        }
+    }
+
+    mesh.into_vertex_array()
+}
+
+/// Flat ring in the XZ plane facing +Y, like a planetary ring. U runs around
+/// the ring and V from the inner to the outer edge.
+pub fn annulus(inner_radius: f32, outer_radius: f32, segments: usize) -> Vec<Vertex> {
+    let segments = segments.max(3);
+    let normal = Vec3::new(0.0, 1.0, 0.0);
+    let mut mesh = IndexedMesh::new();
+
+    for segment in 0..=segments {
+        let u = segment as f32 / segments as f32;
+        let phi = u * 2.0 * PI;
+        let direction = Vec3::new(phi.cos(), 0.0, -phi.sin());
+        mesh.push_vertex(direction * inner_radius, normal, Vec2::new(u, 0.0));
+        mesh.push_vertex(direction * outer_radius, normal, Vec2::new(u, 1.0));
+    }
+
+    for segment in 0..segments as u32 {
+        let inner = segment * 2;
+        mesh.push_quad(inner, inner + 1, inner + 3, inner + 2);
+    }
+
+    mesh.into_vertex_array()
+}
@@ -0,0 +1,361 @@
+// mesh_cache.rs
+//
+// Binary cache for parsed OBJ files, written next to the source as
+// `<file>.meshcache`. Layout, all little endian:
+//
+//     magic "MESHCACH", version u32
+//     source hash u64      FNV-1a of the OBJ and the MTL files it names
+//     payload length u64, payload checksum u64 (FNV-1a of the payload)
+//     payload              materials, then meshes (see `write_payload`)
+//
+// A cache whose version, source hash or checksum doesn't match is ignored
+// and rewritten from the OBJ.
+
+use nalgebra_glm::{Vec2, Vec3};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::color::Color;
+use crate::material::Material;
+use crate::obj::{Obj, ObjMesh};
+
+const MAGIC: &[u8; 8] = b"MESHCACH";
+/// Bump whenever the payload layout or what `Obj::load` produces changes.
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 8 + 4 + 8 + 8 + 8;
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+/// 64-bit FNV-1a, continuing from `hash`.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+pub fn cache_path(filename: &str) -> PathBuf {
+    PathBuf::from(format!("{}.meshcache", filename))
+}
+
+/// Hash of the OBJ together with its `mtllib` files, so editing a material
+/// also invalidates the cache.
+fn source_hash(filename: &str, source: &[u8]) -> u64 {
+    let mut hash = fnv1a(FNV_OFFSET, source);
+    let base_dir = Path::new(filename).parent().unwrap_or(Path::new(""));
+    for line in String::from_utf8_lossy(source).lines() {
+        let mut words = line.split_whitespace();
+        if words.next() != Some("mtllib") {
+            continue;
+        }
+        // One mtllib line can name several files
+        for library in words {
+            hash = fnv1a(hash, library.as_bytes());
+            if let Ok(mtl) = std::fs::read(base_dir.join(library)) {
+                hash = fnv1a(hash, &mtl);
+            }
+        }
+    }
+    hash
+}
+
+/// Writes `bytes` to a temporary file next to `path` and renames it into
+/// place, so a crash or a second viewer never sees a half-written cache.
+fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let temp = PathBuf::from(format!("{}.{}.tmp", path.display(), std::process::id()));
+    std::fs::write(&temp, bytes)
+        .and_then(|()| std::fs::rename(&temp, path))
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp);
+        })
+}
+
+/// Loads `filename` through its cache, parsing the OBJ and refreshing the
+/// cache when it is missing or stale. Failing to write the cache only
+/// costs the speed-up, so it is reported and otherwise ignored.
+pub fn load_obj(filename: &str) -> Result<Obj, tobj::LoadError> {
+    let Ok(source) = std::fs::read(filename) else {
+        return Obj::load(filename);
+    };
+    let hash = source_hash(filename, &source);
+    let path = cache_path(filename);
+    let base_dir = Path::new(filename).parent().unwrap_or(Path::new(""));
+
+    if let Some(obj) = std::fs::read(&path).ok().and_then(|bytes| read_cache(&bytes, hash, base_dir)) {
+        return Ok(obj);
+    }
+
+    let obj = Obj::load(filename)?;
+    if let Err(err) = write_atomically(&path, &write_cache(&obj, hash)) {
+        eprintln!("Could not write mesh cache {}: {}", path.display(), err);
+    }
+    Ok(obj)
+}
+
+fn write_cache(obj: &Obj, source_hash: u64) -> Vec<u8> {
+    let mut payload = Writer(Vec::new());
+    write_payload(&mut payload, obj);
+    let payload = payload.0;
+
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&source_hash.to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&fnv1a(FNV_OFFSET, &payload).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    bytes
+}
+
+/// The cached `Obj`, or `None` when the cache is stale, truncated or corrupt.
+fn read_cache(bytes: &[u8], source_hash: u64, base_dir: &Path) -> Option<Obj> {
+    let mut header = Reader { bytes, position: 0 };
+    if header.take(8)? != MAGIC || header.u32()? != VERSION || header.u64()? != source_hash {
+        return None;
+    }
+    let length = header.u64()? as usize;
+    let checksum = header.u64()?;
+    let payload = bytes.get(HEADER_SIZE..HEADER_SIZE.checked_add(length)?)?;
+    if fnv1a(FNV_OFFSET, payload) != checksum {
+        return None;
+    }
+    read_payload(&mut Reader { bytes: payload, position: 0 }, base_dir)
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn vec3(&mut self, value: &Vec3) {
+        self.f32(value.x);
+        self.f32(value.y);
+        self.f32(value.z);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.0.extend_from_slice(value.as_bytes());
+    }
+
+    /// Empty strings stand for `None`; no MTL map is ever an empty path.
+    fn optional_string(&mut self, value: &Option<String>) {
+        self.string(value.as_deref().unwrap_or(""));
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes.get(self.position..self.position.checked_add(count)?)?;
+        self.position += count;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn vec3(&mut self) -> Option<Vec3> {
+        Some(Vec3::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let length = self.u32()? as usize;
+        String::from_utf8(self.take(length)?.to_vec()).ok()
+    }
+
+    fn optional_string(&mut self) -> Option<Option<String>> {
+        let value = self.string()?;
+        Some((!value.is_empty()).then_some(value))
+    }
+
+    /// `count` items read with `item`, checking first that the data can hold them.
+    fn items<T>(&mut self, item_size: usize, mut item: impl FnMut(&mut Self) -> Option<T>) -> Option<Vec<T>> {
+        let count = self.u32()? as usize;
+        if count.checked_mul(item_size)? > self.bytes.len() - self.position {
+            return None;
+        }
+        (0..count).map(|_| item(self)).collect()
+    }
+}
+
+/// Materials (name, Kd, Ks, Ns, Ke, d, map paths), then meshes (name,
+/// material index plus one or 0 for none, and the vertex, normal, UV,
+/// color and index arrays, each prefixed by its length).
+fn write_payload(out: &mut Writer, obj: &Obj) {
+    out.u32(obj.materials().len() as u32);
+    for material in obj.materials() {
+        out.string(&material.name);
+        out.vec3(&material.diffuse);
+        out.vec3(&material.specular);
+        out.f32(material.shininess);
+        out.vec3(&material.emissive);
+        out.f32(material.dissolve);
+        out.optional_string(&material.diffuse_map);
+        out.optional_string(&material.specular_map);
+        out.optional_string(&material.normal_map);
+    }
+
+    out.u32(obj.meshes().len() as u32);
+    for mesh in obj.meshes() {
+        out.string(&mesh.name);
+        out.u32(mesh.material.map_or(0, |index| index as u32 + 1));
+        out.u32(mesh.vertices.len() as u32);
+        mesh.vertices.iter().for_each(|v| out.vec3(v));
+        out.u32(mesh.normals.len() as u32);
+        mesh.normals.iter().for_each(|n| out.vec3(n));
+        out.u32(mesh.texcoords.len() as u32);
+        for uv in &mesh.texcoords {
+            out.f32(uv.x);
+            out.f32(uv.y);
+        }
+        out.u32(mesh.colors.len() as u32);
+        mesh.colors.iter().for_each(|c| out.vec3(&Vec3::new(c.r, c.g, c.b)));
+        out.u32(mesh.indices.len() as u32);
+        mesh.indices.iter().for_each(|&i| out.u32(i));
+    }
+}
+
+fn read_payload(input: &mut Reader, base_dir: &Path) -> Option<Obj> {
+    let materials = input.items(1, |input| {
+        let mut material = Material {
+            name: input.string()?,
+            diffuse: input.vec3()?,
+            specular: input.vec3()?,
+            shininess: input.f32()?,
+            emissive: input.vec3()?,
+            dissolve: input.f32()?,
+            diffuse_map: input.optional_string()?,
+            specular_map: input.optional_string()?,
+            normal_map: input.optional_string()?,
+            diffuse_texture: None,
+        };
+        material.load_diffuse_texture(base_dir);
+        Some(Arc::new(material))
+    })?;
+
+    let meshes = input.items(1, |input| {
+        let name = input.string()?;
+        let material = match input.u32()? {
+            0 => None,
+            index => Some(index as usize - 1).filter(|&index| index < materials.len()),
+        };
+        let mesh = ObjMesh {
+            name,
+            material,
+            vertices: input.items(12, Reader::vec3)?,
+            normals: input.items(12, Reader::vec3)?,
+            texcoords: input.items(8, |input| Some(Vec2::new(input.f32()?, input.f32()?)))?,
+            colors: input.items(12, |input| input.vec3().map(|c| Color::new(c.x, c.y, c.z)))?,
+            indices: input.items(4, Reader::u32)?,
+        };
+        mesh.indices.iter().all(|&i| (i as usize) < mesh.vertices.len()).then_some(mesh)
+    })?;
+
+    Some(Obj::from_meshes(meshes, materials))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: u64 = 0x1234_5678_9abc_def0;
+
+    /// One textured, colored triangle with a material, and a second part without one.
+    fn sample() -> Obj {
+        let material = Material {
+            name: "gold".to_string(),
+            diffuse: Vec3::new(0.8, 0.6, 0.1),
+            specular: Vec3::new(0.5, 0.5, 0.5),
+            shininess: 64.0,
+            diffuse_map: Some("gold.png".to_string()),
+            ..Material::default()
+        };
+        let triangle = |name: &str, material| ObjMesh {
+            name: name.to_string(),
+            vertices: vec![Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)],
+            normals: vec![Vec3::new(0.0, 0.0, 1.0); 3],
+            texcoords: vec![Vec2::new(0.0, 1.0), Vec2::new(1.0, 1.0), Vec2::new(0.0, 0.0)],
+            colors: vec![Color::new(255.0, 128.0, 0.0); 3],
+            indices: vec![0, 1, 2],
+            material,
+        };
+        Obj::from_meshes(vec![triangle("Body", Some(0)), triangle("Panel", None)], vec![Arc::new(material)])
+    }
+
+    fn read(bytes: &[u8], source_hash: u64) -> Option<Obj> {
+        // The directory only matters for textures, and gold.png doesn't exist anywhere
+        read_cache(bytes, source_hash, Path::new("."))
+    }
+
+    #[test]
+    fn round_trips_meshes_and_materials() {
+        let obj = sample();
+        let cached = read(&write_cache(&obj, HASH), HASH).expect("a fresh cache reads back");
+
+        assert_eq!(cached.materials().len(), 1);
+        let (original, material) = (&obj.materials()[0], &cached.materials()[0]);
+        assert_eq!(material.name, original.name);
+        assert_eq!(material.diffuse, original.diffuse);
+        assert_eq!(material.specular, original.specular);
+        assert_eq!(material.shininess, original.shininess);
+        assert_eq!(material.dissolve, original.dissolve);
+        assert_eq!(material.diffuse_map, original.diffuse_map);
+        assert_eq!(material.normal_map, None);
+
+        assert_eq!(cached.meshes().len(), obj.meshes().len());
+        for (mesh, original) in cached.meshes().iter().zip(obj.meshes()) {
+            assert_eq!(mesh.name, original.name);
+            assert_eq!(mesh.material, original.material);
+            assert_eq!(mesh.vertices, original.vertices);
+            assert_eq!(mesh.normals, original.normals);
+            assert_eq!(mesh.texcoords, original.texcoords);
+            assert_eq!(mesh.indices, original.indices);
+            let channels = |colors: &[Color]| colors.iter().map(|c| (c.r, c.g, c.b)).collect::<Vec<_>>();
+            assert_eq!(channels(&mesh.colors), channels(&original.colors));
+        }
+    }
+
+    #[test]
+    fn rejects_a_corrupt_payload() {
+        let mut bytes = write_cache(&sample(), HASH);
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        assert!(read(&bytes, HASH).is_none());
+    }
+
+    #[test]
+    fn rejects_a_stale_source_hash() {
+        let bytes = write_cache(&sample(), HASH);
+        assert!(read(&bytes, HASH + 1).is_none());
+    }
+
+    #[test]
+    fn rejects_a_truncated_header() {
+        let bytes = write_cache(&sample(), HASH);
+        assert!(read(&bytes[..HEADER_SIZE - 1], HASH).is_none());
+        assert!(read(&bytes[..8], HASH).is_none());
+        assert!(read(&[], HASH).is_none());
+    }
+
+    #[test]
+    fn rejects_another_version() {
+        let mut bytes = write_cache(&sample(), HASH);
+        bytes[8..12].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(read(&bytes, HASH).is_none());
+    }
+}
@@ -0,0 +1,49 @@
+// model.rs
+//
+// Loads a model file with the loader its extension calls for.
+
+use std::fmt;
+use std::path::Path;
+use crate::{gltf_loader, mesh_cache, ply, stl};
+use crate::obj::Obj;
+
+#[derive(Debug)]
+pub enum ModelError {
+    Io(std::io::Error),
+    Obj(tobj::LoadError),
+    Gltf(gltf::Error),
+    /// A PLY or STL file that doesn't follow the format.
+    Invalid(String),
+    UnknownFormat(String),
+    /// `file#Part` named a part the file doesn't have; the file's parts follow.
+    MissingPart(String, Vec<String>),
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelError::Io(err) => write!(f, "could not read model: {}", err),
+            ModelError::Obj(err) => write!(f, "invalid OBJ file: {}", err),
+            ModelError::Gltf(err) => write!(f, "invalid glTF file: {}", err),
+            ModelError::Invalid(message) => write!(f, "invalid model: {}", message),
+            ModelError::UnknownFormat(extension) => {
+                write!(f, "unknown model format \"{}\", expected obj, gltf, glb, ply or stl", extension)
+            }
+            ModelError::MissingPart(name, parts) => write!(f, "no part called {} (parts: {})", name, parts.join(", ")),
+        }
+    }
+}
+
+pub fn load(filename: &str) -> Result<Obj, ModelError> {
+    let extension = Path::new(filename)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "obj" => mesh_cache::load_obj(filename).map_err(ModelError::Obj),
+        "gltf" | "glb" => gltf_loader::load(filename),
+        "ply" => ply::load(filename),
+        "stl" => stl::load(filename),
+        _ => Err(ModelError::UnknownFormat(extension)),
+    }
+}
@@ -0,0 +1,137 @@
+// normals.rs
+//
+// Normal generation for indexed triangle meshes. Normals are produced per
+// triangle corner, so hard edges can be kept without the caller splitting
+// vertices first.
+
+use nalgebra_glm::Vec3;
+use std::collections::HashMap;
+
+/// Crease angle used when smoothing unless the user picks another one.
+pub const DEFAULT_CREASE_ANGLE: f32 = 60.0;
+
+/// How normals are obtained for a mesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NormalMode {
+    /// Keep the file's normals, generating smooth ones only when they are missing.
+    FromFile,
+    /// Angle-weighted average of the faces around each vertex. Faces meeting
+    /// at more than `crease_angle` degrees keep a hard edge between them.
+    Smooth { crease_angle: f32 },
+    /// One normal per face.
+    Flat,
+}
+
+impl NormalMode {
+    /// Parses the `--normals` argument: `file`, `flat`, `smooth` or `smooth:<degrees>`.
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "file" => Some(NormalMode::FromFile),
+            "flat" => Some(NormalMode::Flat),
+            "smooth" => Some(NormalMode::Smooth { crease_angle: DEFAULT_CREASE_ANGLE }),
+            _ => {
+                let degrees = text.strip_prefix("smooth:")?.parse().ok()?;
+                Some(NormalMode::Smooth { crease_angle: degrees })
+            }
+        }
+    }
+}
+
+/// Area-weighted face normal; zero for degenerate triangles.
+fn face_normal(a: &Vec3, b: &Vec3, c: &Vec3) -> Vec3 {
+    (b - a).cross(&(c - a))
+}
+
+/// True when the triangle has (almost) no area, e.g. repeated or collinear corners.
+pub fn is_degenerate(a: &Vec3, b: &Vec3, c: &Vec3) -> bool {
+    let longest = (b - a).magnitude().max((c - b).magnitude()).max((a - c).magnitude());
+    face_normal(a, b, c).magnitude() <= f32::EPSILON * longest * longest
+}
+
+/// Interior angle at `corner` between the edges to `next` and `prev`.
+fn corner_angle(corner: &Vec3, next: &Vec3, prev: &Vec3) -> f32 {
+    let (u, v) = (next - corner, prev - corner);
+    let denominator = u.magnitude() * v.magnitude();
+    if denominator <= 0.0 {
+        0.0
+    } else {
+        (u.dot(&v) / denominator).clamp(-1.0, 1.0).acos()
+    }
+}
+
+/// Indices of the triangles in `indices` with no area.
+pub fn degenerate_triangles(positions: &[Vec3], indices: &[u32]) -> Vec<usize> {
+    indices
+        .chunks_exact(3)
+        .enumerate()
+        .filter(|(_, tri)| {
+            is_degenerate(&positions[tri[0] as usize], &positions[tri[1] as usize], &positions[tri[2] as usize])
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// One normal per entry of `indices`, following `mode` (`FromFile` smooths with
+/// the default crease angle). Vertices at the same position are treated as
+/// one even when the file duplicated them for a UV seam. Degenerate
+/// triangles don't contribute and take their neighbours' normal, or +Y when
+/// they have none.
+pub fn corner_normals(positions: &[Vec3], indices: &[u32], mode: NormalMode) -> Vec<Vec3> {
+    let triangles: Vec<[usize; 3]> = indices
+        .chunks_exact(3)
+        .map(|tri| [tri[0] as usize, tri[1] as usize, tri[2] as usize])
+        .collect();
+    let face_normals: Vec<Option<Vec3>> = triangles
+        .iter()
+        .map(|&[a, b, c]| {
+            let (a, b, c) = (&positions[a], &positions[b], &positions[c]);
+            (!is_degenerate(a, b, c)).then(|| face_normal(a, b, c).normalize())
+        })
+        .collect();
+    let fallback = Vec3::new(0.0, 1.0, 0.0);
+
+    let crease_angle = match mode {
+        NormalMode::Flat => {
+            return face_normals
+                .iter()
+                .flat_map(|normal| [normal.unwrap_or(fallback); 3])
+                .collect();
+        }
+        NormalMode::Smooth { crease_angle } => crease_angle,
+        NormalMode::FromFile => DEFAULT_CREASE_ANGLE,
+    };
+    let min_cos = crease_angle.to_radians().cos();
+
+    // Faces around each position, with the angle they span there
+    let key = |p: &Vec3| (p.x.to_bits(), p.y.to_bits(), p.z.to_bits());
+    let mut around: HashMap<(u32, u32, u32), Vec<(usize, f32)>> = HashMap::new();
+    for (face, tri) in triangles.iter().enumerate() {
+        if face_normals[face].is_none() {
+            continue;
+        }
+        for corner in 0..3 {
+            let (p, next, prev) = (&positions[tri[corner]], &positions[tri[(corner + 1) % 3]], &positions[tri[(corner + 2) % 3]]);
+            around.entry(key(p)).or_default().push((face, corner_angle(p, next, prev)));
+        }
+    }
+
+    let mut normals = Vec::with_capacity(indices.len());
+    for (face, tri) in triangles.iter().enumerate() {
+        for &vertex in tri {
+            let neighbours = around.get(&key(&positions[vertex])).map_or(&[][..], Vec::as_slice);
+            let mut sum = Vec3::new(0.0, 0.0, 0.0);
+            for &(other, angle) in neighbours {
+                let other_normal = face_normals[other].unwrap();
+                let within_crease = match face_normals[face] {
+                    Some(normal) => normal.dot(&other_normal) >= min_cos,
+                    None => true,
+                };
+                if within_crease {
+                    sum += other_normal * angle;
+                }
+            }
+            normals.push(if sum.magnitude() > 0.0 { sum.normalize() } else { face_normals[face].unwrap_or(fallback) });
+        }
+    }
+    normals
+}
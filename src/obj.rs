@@ -1,31 +1,118 @@
 // obj.rs
 
 use nalgebra_glm::{Vec2, Vec3}; 
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+use crate::color::Color;
+use crate::material::Material;
+use crate::normals::{NormalMode, corner_normals, degenerate_triangles};
 use crate::vertex::Vertex;
 use tobj;
 
 pub struct Obj {
-    meshes: Vec<Mesh>,
+    meshes: Vec<ObjMesh>,
+    materials: Vec<Arc<Material>>,
 }
 
-struct Mesh {
-    vertices: Vec<Vec3>,
-    normals: Vec<Vec3>,
-    texcoords: Vec<Vec2>,
-    indices: Vec<u32>,
+/// Axis-aligned box around a set of points.
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl BoundingBox {
+    /// The box around `points`, or a zero box at the origin when there are none.
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vec3>) -> Self {
+        let mut points = points.into_iter();
+        let Some(first) = points.next() else {
+            return BoundingBox { min: Vec3::zeros(), max: Vec3::zeros() };
+        };
+        points.fold(BoundingBox { min: *first, max: *first }, |bounds, p| BoundingBox {
+            min: bounds.min.inf(p),
+            max: bounds.max.sup(p),
+        })
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+}
+
+/// One model or group of the file, as it appears in `get_vertex_array`.
+pub struct ObjPart {
+    /// Name from the `o` or `g` statement.
+    pub name: String,
+    pub bounds: BoundingBox,
+    /// Vertices of this part within `get_vertex_array`.
+    pub vertex_range: Range<usize>,
+    pub material: Option<Arc<Material>>,
+}
+
+/// Indexed geometry of one part. Other loaders fill these in to build an `Obj`.
+pub struct ObjMesh {
+    pub name: String,
+    pub vertices: Vec<Vec3>,
+    /// Empty, or one per vertex.
+    pub normals: Vec<Vec3>,
+    /// Empty, or one per vertex, with V growing downwards.
+    pub texcoords: Vec<Vec2>,
+    /// Empty, or one per vertex, in the 0-255 range.
+    pub colors: Vec<Color>,
+    pub indices: Vec<u32>,
+    /// Index into `Obj::materials`, from the mesh's `usemtl`.
+    pub material: Option<usize>,
+}
+
+impl ObjMesh {
+    fn has_normals(&self) -> bool {
+        !self.vertices.is_empty() && self.normals.len() == self.vertices.len()
+    }
+
+    /// Replaces the normals with generated ones. Normals are computed per
+    /// triangle corner, so the mesh is expanded to one vertex per corner.
+    fn generate_normals(&mut self, mode: NormalMode) {
+        let normals = corner_normals(&self.vertices, &self.indices, mode);
+        self.vertices = self.indices.iter().map(|&i| self.vertices[i as usize]).collect();
+        if !self.texcoords.is_empty() {
+            self.texcoords = self.indices.iter().map(|&i| self.texcoords[i as usize]).collect();
+        }
+        if !self.colors.is_empty() {
+            self.colors = self.indices.iter().map(|&i| self.colors[i as usize]).collect();
+        }
+        self.normals = normals;
+        self.indices = (0..self.vertices.len() as u32).collect();
+    }
 }
 
 impl Obj {
+    /// Loads the file, generating smooth normals for meshes that have none.
     pub fn load(filename: &str) -> Result<Self, tobj::LoadError> {
-        let (models, _) = tobj::load_obj(filename, &tobj::LoadOptions {
+        let (models, materials) = tobj::load_obj(filename, &tobj::LoadOptions {
             single_index: true,
             triangulate: true,
             ..Default::default()
         })?;
 
+        // A missing or broken MTL file only costs the materials, not the geometry
+        let base_dir = Path::new(filename).parent().unwrap_or(Path::new(""));
+        let materials: Vec<Arc<Material>> = match materials {
+            Ok(materials) => materials
+                .into_iter()
+                .map(|material| Arc::new(Material::from_mtl(material, base_dir)))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
         let meshes = models.into_iter().map(|model| {
             let mesh = model.mesh;
-            Mesh {
+            ObjMesh {
+                name: model.name,
                 vertices: mesh.positions.chunks(3)
                     .map(|v| Vec3::new(v[0], v[1], v[2]))
                     .collect(),
@@ -35,33 +122,111 @@ impl Obj {
                 texcoords: mesh.texcoords.chunks(2)
                     .map(|t| Vec2::new(t[0], 1.0 - t[1]))
                     .collect(),
+                colors: mesh.vertex_color.chunks(3)
+                    .map(|c| Color::new(c[0] * 255.0, c[1] * 255.0, c[2] * 255.0))
+                    .collect(),
                 indices: mesh.indices,
+                material: mesh.material_id.filter(|&id| id < materials.len()),
             }
         }).collect();
 
-        Ok(Obj {meshes})
+        Ok(Obj::from_meshes(meshes, materials))
+    }
+
+    /// Builds an `Obj` from geometry read by another loader, generating smooth
+    /// normals for meshes that have none, like `load`.
+    pub fn from_meshes(meshes: Vec<ObjMesh>, materials: Vec<Arc<Material>>) -> Self {
+        let mut obj = Obj {meshes, materials};
+        obj.generate_normals(NormalMode::FromFile);
+        obj
     }
-    
+
+    /// Regenerates every mesh's normals following `mode`. With `FromFile` only
+    /// meshes that lack normals get generated ones.
+    pub fn generate_normals(&mut self, mode: NormalMode) {
+        for mesh in &mut self.meshes {
+            if mode != NormalMode::FromFile || !mesh.has_normals() {
+                mesh.generate_normals(mode);
+            }
+        }
+    }
+
+    /// Triangles with no area, as (mesh, triangle) index pairs. They render as
+    /// nothing but usually point at a broken export.
+    pub fn degenerate_triangles(&self) -> Vec<(usize, usize)> {
+        self.meshes
+            .iter()
+            .enumerate()
+            .flat_map(|(mesh_index, mesh)| {
+                degenerate_triangles(&mesh.vertices, &mesh.indices)
+                    .into_iter()
+                    .map(move |triangle| (mesh_index, triangle))
+            })
+            .collect()
+    }
+
+    pub fn meshes(&self) -> &[ObjMesh] {
+        &self.meshes
+    }
+
+    pub fn materials(&self) -> &[Arc<Material>] {
+        &self.materials
+    }
+
     pub fn get_vertex_array(&self) -> Vec<Vertex> {
         let mut vertices = Vec::new();
-
         for mesh in &self.meshes {
-            for &index in &mesh.indices {
-                let position = mesh.vertices[index as usize];
-                let normal = mesh.normals.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec3::new(0.0,1.0,0.0));
-                let tex_coords = mesh.texcoords.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec2::new(0.0,0.0));
+            Self::push_mesh_vertices(mesh, &mut vertices);
+        }
+        vertices
+    }
 
-                vertices.push(Vertex::new(position, normal, tex_coords));
+    /// Every model and group of the file, in file order. A model split by
+    /// `usemtl` shows up once per material, under the same name.
+    pub fn parts(&self) -> Vec<ObjPart> {
+        let mut start = 0;
+        self.meshes.iter().map(|mesh| {
+            let end = start + mesh.indices.len();
+            let part = ObjPart {
+                name: mesh.name.clone(),
+                bounds: BoundingBox::from_points(mesh.indices.iter().map(|&i| &mesh.vertices[i as usize])),
+                vertex_range: start..end,
+                material: mesh.material.and_then(|id| self.materials.get(id).cloned()),
+            };
+            start = end;
+            part
+        }).collect()
+    }
 
-            }
-        }
+    /// Index of the first part called `name`.
+    pub fn find_part(&self, name: &str) -> Option<usize> {
+        self.meshes.iter().position(|mesh| mesh.name == name)
+    }
 
+    /// Vertex array of a single part, the `vertex_range` slice of `get_vertex_array`.
+    pub fn get_part_vertex_array(&self, index: usize) -> Vec<Vertex> {
+        let mut vertices = Vec::new();
+        Self::push_mesh_vertices(&self.meshes[index], &mut vertices);
         vertices
     }
 
+    fn push_mesh_vertices(mesh: &ObjMesh, vertices: &mut Vec<Vertex>) {
+        for &index in &mesh.indices {
+            let position = mesh.vertices[index as usize];
+            let normal = mesh.normals.get(index as usize)
+                .cloned()
+                .unwrap_or(Vec3::new(0.0,1.0,0.0));
+            let tex_coords = mesh.texcoords.get(index as usize)
+                .cloned()
+                .unwrap_or(Vec2::new(0.0,0.0));
+
+            let mut vertex = Vertex::new(position, normal, tex_coords);
+            if let Some(color) = mesh.colors.get(index as usize) {
+                vertex.color = *color;
+            }
+            vertices.push(vertex);
+        }
+    }
+
 
 }
\ No newline at end of file
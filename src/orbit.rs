@@ -0,0 +1,172 @@
+// orbit.rs
+
+use nalgebra_glm::{Vec3, Vec4};
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use crate::framebuffer::Framebuffer;
+use crate::Uniforms;
+
+/// Keplerian orbit around the parent body, in the parent's frame.
+#[derive(Clone, Debug)]
+pub struct Orbit {
+    pub semi_major_axis: f32,
+    pub eccentricity: f32,
+    /// Tilt of the orbital plane around the X axis, in radians.
+    pub inclination: f32,
+    /// Seconds of simulation time per revolution.
+    pub period: f32,
+    /// Mean anomaly at time 0, in radians.
+    pub phase: f32,
+}
+
+impl Orbit {
+    pub fn circular(radius: f32, period: f32) -> Self {
+        Orbit {
+            semi_major_axis: radius,
+            eccentricity: 0.0,
+            inclination: 0.0,
+            period,
+            phase: 0.0,
+        }
+    }
+
+    /// Mean anomaly at `time`, which is also the orbital angle for circular orbits.
+    pub fn mean_anomaly(&self, time: f32) -> f32 {
+        self.phase + 2.0 * PI * time / self.period
+    }
+
+    /// Solves Kepler's equation M = E - e sin E with a few Newton steps.
+    fn eccentric_anomaly(&self, mean_anomaly: f32) -> f32 {
+        let mut e = mean_anomaly;
+        for _ in 0..5 {
+            e -= (e - self.eccentricity * e.sin() - mean_anomaly) / (1.0 - self.eccentricity * e.cos());
+        }
+        e
+    }
+
+    fn point_at_eccentric_anomaly(&self, e: f32) -> Vec3 {
+        let a = self.semi_major_axis;
+        let b = a * (1.0 - self.eccentricity * self.eccentricity).sqrt();
+        let x = a * (e.cos() - self.eccentricity);
+        let z = b * e.sin();
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+        Vec3::new(x, -z * sin_i, z * cos_i)
+    }
+
+    /// Offset from the parent body at `time`.
+    pub fn position(&self, time: f32) -> Vec3 {
+        self.point_at_eccentric_anomaly(self.eccentric_anomaly(self.mean_anomaly(time)))
+    }
+
+    /// Closed polyline approximating the orbit ellipse, relative to the parent.
+    pub fn path(&self, segments: usize) -> Vec<Vec3> {
+        (0..=segments)
+            .map(|i| self.point_at_eccentric_anomaly(2.0 * PI * i as f32 / segments as f32))
+            .collect()
+    }
+}
+
+/// Recent world positions of a moving body, oldest first.
+pub struct Trail {
+    points: VecDeque<Vec3>,
+    max_points: usize,
+    min_spacing: f32,
+}
+
+impl Trail {
+    pub fn new(max_points: usize, min_spacing: f32) -> Self {
+        Trail {
+            points: VecDeque::with_capacity(max_points),
+            max_points,
+            min_spacing,
+        }
+    }
+
+    /// Records `position` unless it is too close to the previous sample.
+    pub fn push(&mut self, position: Vec3) {
+        if let Some(last) = self.points.back() {
+            if (position - last).magnitude() < self.min_spacing {
+                return;
+            }
+        }
+        if self.points.len() == self.max_points {
+            self.points.pop_front();
+        }
+        self.points.push_back(position);
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = &Vec3> {
+        self.points.iter()
+    }
+}
+
+/// Projects a world-space point to screen space through the view, projection
+/// and viewport matrices. Returns `None` for points behind the camera.
+pub fn project_point(point: &Vec3, uniforms: &Uniforms) -> Option<Vec3> {
+    let clip = to_clip(point, uniforms);
+    if clip.w <= 0.0 {
+        return None;
+    }
+    Some(clip_to_screen(&clip, uniforms))
+}
+
+fn to_clip(point: &Vec3, uniforms: &Uniforms) -> Vec4 {
+    uniforms.projection_matrix * uniforms.view_matrix * Vec4::new(point.x, point.y, point.z, 1.0)
+}
+
+fn clip_to_screen(clip: &Vec4, uniforms: &Uniforms) -> Vec3 {
+    let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+    let screen = uniforms.viewport_matrix * ndc;
+    Vec3::new(screen.x, screen.y, screen.z)
+}
+
+/// Projects the segment between two world-space points to screen space,
+/// cutting off the part in front of the near plane first. Without the cut a
+/// point just in front of the camera divides by a tiny `w` and lands far off
+/// screen. Returns `None` when the whole segment is behind the near plane.
+pub fn project_segment(from: &Vec3, to: &Vec3, uniforms: &Uniforms) -> Option<(Vec3, Vec3)> {
+    let (mut a, mut b) = (to_clip(from, uniforms), to_clip(to, uniforms));
+    // Inside the near plane z >= -w, i.e. z + w >= 0
+    let (da, db) = (a.z + a.w, b.z + b.w);
+    if da < 0.0 && db < 0.0 {
+        return None;
+    }
+    if da < 0.0 {
+        a += (b - a) * (da / (da - db));
+    } else if db < 0.0 {
+        b += (a - b) * (db / (db - da));
+    }
+    if a.w <= 0.0 || b.w <= 0.0 {
+        return None;
+    }
+    Some((clip_to_screen(&a, uniforms), clip_to_screen(&b, uniforms)))
+}
+
+fn draw_segment(framebuffer: &mut Framebuffer, from: Vec3, to: Vec3, color: u32, alpha: f32, antialiased: bool) {
+    if antialiased {
+        framebuffer.line_aa(from, to, color, alpha);
+    } else {
+        framebuffer.line(from, to, color, alpha);
+    }
+}
+
+/// Draws a world-space polyline with a single opacity.
+pub fn draw_path(framebuffer: &mut Framebuffer, uniforms: &Uniforms, points: &[Vec3], color: u32, alpha: f32, antialiased: bool) {
+    for pair in points.windows(2) {
+        if let Some((from, to)) = project_segment(&pair[0], &pair[1], uniforms) {
+            draw_segment(framebuffer, from, to, color, alpha, antialiased);
+        }
+    }
+}
+
+/// Draws a trail fading from transparent at its oldest point to opaque at the newest.
+pub fn draw_trail(framebuffer: &mut Framebuffer, uniforms: &Uniforms, trail: &Trail, color: u32, antialiased: bool) {
+    let points: Vec<Vec3> = trail.points().copied().collect();
+    let count = points.len();
+    for (i, pair) in points.windows(2).enumerate() {
+        if let Some((from, to)) = project_segment(&pair[0], &pair[1], uniforms) {
+            let alpha = (i + 1) as f32 / count as f32;
+            draw_segment(framebuffer, from, to, color, alpha, antialiased);
+        }
+    }
+}
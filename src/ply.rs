@@ -0,0 +1,371 @@
+// ply.rs
+//
+// Reader for ASCII and binary (little or big endian) PLY files. Vertex
+// positions, normals, colors and UVs are read; faces with more than three
+// corners are split into fans. Other elements are skipped.
+
+use nalgebra_glm::{Vec2, Vec3};
+use crate::color::Color;
+use crate::model::ModelError;
+use crate::obj::{Obj, ObjMesh};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "char" | "int8" => Some(ScalarType::I8),
+            "uchar" | "uint8" => Some(ScalarType::U8),
+            "short" | "int16" => Some(ScalarType::I16),
+            "ushort" | "uint16" => Some(ScalarType::U16),
+            "int" | "int32" => Some(ScalarType::I32),
+            "uint" | "uint32" => Some(ScalarType::U32),
+            "float" | "float32" => Some(ScalarType::F32),
+            "double" | "float64" => Some(ScalarType::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::I8 | ScalarType::U8 => 1,
+            ScalarType::I16 | ScalarType::U16 => 2,
+            ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        !matches!(self, ScalarType::F32 | ScalarType::F64)
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar { name: String, kind: ScalarType },
+    List { name: String, count: ScalarType, item: ScalarType },
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar { name, .. } | Property::List { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads values one at a time from the body, whatever its format.
+struct Reader<'a> {
+    format: Format,
+    bytes: &'a [u8],
+    position: usize,
+    /// Whitespace separated values of the ASCII body.
+    tokens: std::str::SplitAsciiWhitespace<'a>,
+}
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, kind: ScalarType) -> Result<f64, ModelError> {
+        if self.format == Format::Ascii {
+            let token = self.tokens.next().ok_or_else(|| invalid("the file ends in the middle of an element"))?;
+            return token.parse().map_err(|_| invalid(&format!("\"{}\" is not a number", token)));
+        }
+
+        let size = kind.size();
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + size)
+            .ok_or_else(|| invalid("the file ends in the middle of an element"))?;
+        self.position += size;
+        let mut buffer = [0u8; 8];
+        buffer[..size].copy_from_slice(bytes);
+        if self.format == Format::BinaryBigEndian {
+            buffer[..size].reverse();
+        }
+        Ok(match kind {
+            ScalarType::I8 => buffer[0] as i8 as f64,
+            ScalarType::U8 => buffer[0] as f64,
+            ScalarType::I16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+            ScalarType::U16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+            ScalarType::I32 => i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+            ScalarType::U32 => u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+            ScalarType::F32 => f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+            ScalarType::F64 => f64::from_le_bytes(buffer),
+        })
+    }
+}
+
+fn invalid(message: &str) -> ModelError {
+    ModelError::Invalid(format!("PLY: {}", message))
+}
+
+/// Parses the header, returning the format, the elements and where the body starts.
+fn parse_header(bytes: &[u8]) -> Result<(Format, Vec<Element>, usize), ModelError> {
+    const END: &[u8] = b"end_header";
+    let end = bytes
+        .windows(END.len())
+        .position(|window| window == END)
+        .ok_or_else(|| invalid("missing end_header"))?;
+    let body_start = bytes[end..]
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .map_or(bytes.len(), |newline| end + newline + 1);
+    let header = std::str::from_utf8(&bytes[..end]).map_err(|_| invalid("the header is not text"))?;
+
+    let mut lines = header.lines();
+    if lines.next().map(str::trim) != Some("ply") {
+        return Err(invalid("the file doesn't start with \"ply\""));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", kind, _version] => {
+                format = Some(match *kind {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    other => return Err(invalid(&format!("unknown format {}", other))),
+                });
+            }
+            ["element", name, count] => {
+                let count = count.parse().map_err(|_| invalid(&format!("bad count for element {}", name)))?;
+                elements.push(Element { name: name.to_string(), count, properties: Vec::new() });
+            }
+            ["property", "list", count, item, name] => {
+                let property = Property::List {
+                    name: name.to_string(),
+                    count: ScalarType::parse(count).ok_or_else(|| invalid(&format!("unknown type {}", count)))?,
+                    item: ScalarType::parse(item).ok_or_else(|| invalid(&format!("unknown type {}", item)))?,
+                };
+                elements.last_mut().ok_or_else(|| invalid("property before any element"))?.properties.push(property);
+            }
+            ["property", kind, name] => {
+                let property = Property::Scalar {
+                    name: name.to_string(),
+                    kind: ScalarType::parse(kind).ok_or_else(|| invalid(&format!("unknown type {}", kind)))?,
+                };
+                elements.last_mut().ok_or_else(|| invalid("property before any element"))?.properties.push(property);
+            }
+            _ => return Err(invalid(&format!("unexpected header line \"{}\"", line.trim()))),
+        }
+    }
+
+    let format = format.ok_or_else(|| invalid("missing format line"))?;
+    Ok((format, elements, body_start))
+}
+
+pub fn load(filename: &str) -> Result<Obj, ModelError> {
+    let bytes = std::fs::read(filename).map_err(ModelError::Io)?;
+    let name = std::path::Path::new(filename)
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    parse(&bytes, name)
+}
+
+/// Reads a whole PLY file held in memory as a single part called `name`.
+fn parse(bytes: &[u8], name: String) -> Result<Obj, ModelError> {
+    let (format, elements, body_start) = parse_header(bytes)?;
+    let body = &bytes[body_start..];
+    let text = if format == Format::Ascii {
+        std::str::from_utf8(body).map_err(|_| invalid("the ASCII body is not text"))?
+    } else {
+        ""
+    };
+    let mut reader = Reader { format, bytes: body, position: 0, tokens: text.split_ascii_whitespace() };
+
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+    let mut texcoords = Vec::new();
+    let mut indices = Vec::new();
+
+    for element in &elements {
+        let find = |names: &[&str]| element.properties.iter().position(|p| names.contains(&p.name()));
+        let (x, y, z) = (find(&["x"]), find(&["y"]), find(&["z"]));
+        let (nx, ny, nz) = (find(&["nx"]), find(&["ny"]), find(&["nz"]));
+        let (red, green, blue) = (find(&["red", "r"]), find(&["green", "g"]), find(&["blue", "b"]));
+        let (u, v) = (find(&["u", "s", "texture_u", "texture_s"]), find(&["v", "t", "texture_v", "texture_t"]));
+        let face_list = find(&["vertex_indices", "vertex_index"]);
+        // Integer colors go from 0 to 255, float ones from 0 to 1
+        let color_scale = match red.map(|i| &element.properties[i]) {
+            Some(Property::Scalar { kind, .. }) if !kind.is_integer() => 255.0,
+            _ => 1.0,
+        };
+
+        for _ in 0..element.count {
+            let mut values = Vec::with_capacity(element.properties.len());
+            let mut list = Vec::new();
+            for (index, property) in element.properties.iter().enumerate() {
+                match property {
+                    Property::Scalar { kind, .. } => values.push(reader.read(*kind)?),
+                    Property::List { count, item, .. } => {
+                        values.push(0.0);
+                        let count = reader.read(*count)? as usize;
+                        let items = (0..count).map(|_| reader.read(*item)).collect::<Result<Vec<f64>, _>>()?;
+                        if Some(index) == face_list {
+                            list = items;
+                        }
+                    }
+                }
+            }
+            let value = |i: Option<usize>| i.map(|i| values[i] as f32);
+
+            match element.name.as_str() {
+                "vertex" => {
+                    let (Some(x), Some(y), Some(z)) = (value(x), value(y), value(z)) else {
+                        return Err(invalid("vertices need x, y and z"));
+                    };
+                    vertices.push(Vec3::new(x, y, z));
+                    if let (Some(nx), Some(ny), Some(nz)) = (value(nx), value(ny), value(nz)) {
+                        normals.push(Vec3::new(nx, ny, nz));
+                    }
+                    if let (Some(r), Some(g), Some(b)) = (value(red), value(green), value(blue)) {
+                        colors.push(Color::new(r * color_scale, g * color_scale, b * color_scale));
+                    }
+                    if let (Some(u), Some(v)) = (value(u), value(v)) {
+                        // PLY puts the UV origin at the bottom left like OBJ
+                        texcoords.push(Vec2::new(u, 1.0 - v));
+                    }
+                }
+                "face" => {
+                    for i in 1..list.len().saturating_sub(1) {
+                        indices.extend_from_slice(&[list[0] as u32, list[i] as u32, list[i + 1] as u32]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(&index) = indices.iter().find(|&&index| index as usize >= vertices.len()) {
+        return Err(invalid(&format!("face uses vertex {} but there are only {}", index, vertices.len())));
+    }
+
+    let mesh = ObjMesh { name, vertices, normals, texcoords, colors, indices, material: None };
+    Ok(Obj::from_meshes(vec![mesh], Vec::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "\
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 2
+property list uchar int vertex_indices
+end_header
+";
+
+    /// A unit square in the XY plane with red corners, as a quad and a triangle.
+    const CORNERS: [[f32; 3]; 4] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]];
+    const FACES: [&[i32]; 2] = [&[0, 1, 2, 3], &[0, 2, 3]];
+
+    fn binary(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian { "binary_big_endian" } else { "binary_little_endian" };
+        let mut bytes = format!("ply\nformat {} 1.0\n{}", format, HEADER).into_bytes();
+        for corner in CORNERS {
+            for value in corner {
+                bytes.extend_from_slice(&if big_endian { value.to_be_bytes() } else { value.to_le_bytes() });
+            }
+            bytes.extend_from_slice(&[255, 0, 0]);
+        }
+        for face in FACES {
+            bytes.push(face.len() as u8);
+            for &index in face {
+                bytes.extend_from_slice(&if big_endian { index.to_be_bytes() } else { index.to_le_bytes() });
+            }
+        }
+        bytes
+    }
+
+    fn invalid_message(result: Result<Obj, ModelError>) -> String {
+        match result {
+            Err(ModelError::Invalid(message)) => message,
+            Err(other) => panic!("expected an invalid model, got {}", other),
+            Ok(_) => panic!("expected an invalid model, but it loaded"),
+        }
+    }
+
+    fn check_square(obj: &Obj) {
+        let mesh = &obj.meshes()[0];
+        assert_eq!(mesh.name, "square");
+        // Normals are generated for files without them, which may unweld the
+        // vertices, so compare the triangle corners. The quad becomes a fan.
+        let corners: Vec<Vec3> = mesh.indices.iter().map(|&i| mesh.vertices[i as usize]).collect();
+        let expected: Vec<Vec3> = [0, 1, 2, 0, 2, 3, 0, 2, 3].iter()
+            .map(|&i| Vec3::new(CORNERS[i][0], CORNERS[i][1], CORNERS[i][2]))
+            .collect();
+        assert_eq!(corners, expected);
+        assert_eq!(mesh.colors.len(), mesh.vertices.len());
+        assert!(mesh.colors.iter().all(|c| (c.r, c.g, c.b) == (255.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn reads_ascii_files() {
+        let text = format!(
+            "ply\nformat ascii 1.0\ncomment made by hand\n{}\
+             0 0 0 255 0 0\n1 0 0 255 0 0\n1 1 0 255 0 0\n0 1 0 255 0 0\n4 0 1 2 3\n3 0 2 3\n",
+            HEADER,
+        );
+        check_square(&parse(text.as_bytes(), "square".to_string()).expect("the ASCII square loads"));
+    }
+
+    #[test]
+    fn reads_binary_files_of_both_endiannesses() {
+        check_square(&parse(&binary(false), "square".to_string()).expect("the little endian square loads"));
+        check_square(&parse(&binary(true), "square".to_string()).expect("the big endian square loads"));
+    }
+
+    #[test]
+    fn rejects_truncated_binary_files() {
+        let bytes = binary(false);
+        let message = invalid_message(parse(&bytes[..bytes.len() - 3], "square".to_string()));
+        assert_eq!(message, "PLY: the file ends in the middle of an element");
+    }
+
+    #[test]
+    fn rejects_faces_past_the_vertices() {
+        let text = format!("ply\nformat ascii 1.0\n{}0 0 0 1 1 1\n1 0 0 1 1 1\n1 1 0 1 1 1\n0 1 0 1 1 1\n3 0 1 4\n3 0 1 2\n", HEADER);
+        let message = invalid_message(parse(text.as_bytes(), "square".to_string()));
+        assert_eq!(message, "PLY: face uses vertex 4 but there are only 4");
+    }
+
+    #[test]
+    fn rejects_files_without_a_header() {
+        let message = invalid_message(parse(b"solid cube\nendsolid cube\n", "cube".to_string()));
+        assert_eq!(message, "PLY: missing end_header");
+    }
+}
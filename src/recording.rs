@@ -0,0 +1,108 @@
+// recording.rs
+//
+// Destinations for recorded frames: a directory of numbered PNGs, an
+// animated GIF, or an uncompressed YUV4MPEG2 (.y4m) stream that ffmpeg
+// reads directly, e.g. `ffmpeg -i orbit.y4m orbit.mp4`.
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, ImageError, ImageResult, RgbaImage};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use crate::framebuffer::Framebuffer;
+use crate::headless::save_png;
+
+/// NeuQuant speed for building each GIF frame's palette, from 1 (best, and
+/// several times slower) to 30.
+const GIF_QUANTIZER_SPEED: i32 = 10;
+
+pub enum FrameWriter {
+    PngSequence { dir: String, next: usize },
+    Gif { encoder: GifEncoder<BufWriter<File>>, delay: Delay },
+    Y4m { out: BufWriter<File>, width: usize, height: usize },
+}
+
+impl FrameWriter {
+    /// Picks the format from `target`: a `.gif` or `.y4m` file, or else a
+    /// directory to fill with `frame_00000.png` and so on. `fps` is the
+    /// playback rate stored in GIF and Y4M files.
+    pub fn create(target: &str, width: usize, height: usize, fps: f32) -> ImageResult<Self> {
+        let extension = Path::new(target)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "gif" => {
+                let mut encoder = GifEncoder::new_with_speed(BufWriter::new(File::create(target)?), GIF_QUANTIZER_SPEED);
+                encoder.set_repeat(Repeat::Infinite)?;
+                let delay = Delay::from_numer_denom_ms(1000, fps.round().max(1.0) as u32);
+                Ok(FrameWriter::Gif { encoder, delay })
+            }
+            "y4m" => {
+                let mut out = BufWriter::new(File::create(target)?);
+                // 4:4:4 keeps the conversion per pixel, with no chroma subsampling
+                writeln!(out, "YUV4MPEG2 W{} H{} F{}:1000 Ip A1:1 C444", width, height, (fps * 1000.0).round() as u32)?;
+                Ok(FrameWriter::Y4m { out, width, height })
+            }
+            _ => {
+                std::fs::create_dir_all(target).map_err(ImageError::IoError)?;
+                Ok(FrameWriter::PngSequence { dir: target.to_string(), next: 0 })
+            }
+        }
+    }
+
+    pub fn write(&mut self, framebuffer: &Framebuffer) -> ImageResult<()> {
+        match self {
+            FrameWriter::PngSequence { dir, next } => {
+                save_png(framebuffer, &format!("{}/frame_{:05}.png", dir, next))?;
+                *next += 1;
+            }
+            FrameWriter::Gif { encoder, delay } => {
+                let rgba = framebuffer.buffer.iter()
+                    .flat_map(|pixel| [(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8, 255])
+                    .collect();
+                let image = RgbaImage::from_raw(framebuffer.width as u32, framebuffer.height as u32, rgba)
+                    .expect("the color buffer holds one value per pixel");
+                encoder.encode_frame(Frame::from_parts(image, 0, 0, *delay))?;
+            }
+            FrameWriter::Y4m { out, width, height } => {
+                if (framebuffer.width, framebuffer.height) != (*width, *height) {
+                    return Err(ImageError::IoError(std::io::Error::other("Y4M frames must all have the same size")));
+                }
+                let mut planes: [Vec<u8>; 3] = std::array::from_fn(|_| Vec::with_capacity(framebuffer.buffer.len()));
+                for &pixel in &framebuffer.buffer {
+                    for (plane, value) in planes.iter_mut().zip(ycbcr(pixel)) {
+                        plane.push(value);
+                    }
+                }
+                out.write_all(b"FRAME\n")?;
+                for plane in &planes {
+                    out.write_all(plane)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Flushes what is still buffered. The GIF trailer is written when the encoder is dropped.
+    pub fn finish(self) -> ImageResult<()> {
+        match self {
+            FrameWriter::PngSequence { .. } => {}
+            FrameWriter::Gif { encoder, .. } => drop(encoder),
+            FrameWriter::Y4m { mut out, .. } => out.flush()?,
+        }
+        Ok(())
+    }
+}
+
+/// BT.601 studio-range Y, Cb and Cr of a 0xRRGGBB pixel, the default ffmpeg
+/// assumes for Y4M input.
+fn ycbcr(pixel: u32) -> [u8; 3] {
+    let r = ((pixel >> 16) & 0xFF) as f32 / 255.0;
+    let g = ((pixel >> 8) & 0xFF) as f32 / 255.0;
+    let b = (pixel & 0xFF) as f32 / 255.0;
+    let y = 16.0 + 65.481 * r + 128.553 * g + 24.966 * b;
+    let cb = 128.0 - 37.797 * r - 74.203 * g + 112.0 * b;
+    let cr = 128.0 + 112.0 * r - 93.786 * g - 18.214 * b;
+    [y, cb, cr].map(|value| value.round().clamp(0.0, 255.0) as u8)
+}
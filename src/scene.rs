@@ -0,0 +1,209 @@
+// scene.rs
+
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use std::sync::Arc;
+use crate::lod::Lod;
+use crate::material::Material;
+use crate::orbit::Orbit;
+use crate::terrain::{self, Displacement};
+
+/// Indices into the mesh list loaded by `main`.
+pub const SPHERE_MESH: usize = 0;
+pub const RING_MESH: usize = 1;
+pub const UV_SPHERE_MESH: usize = 2;
+pub const ICOSPHERE_MESH: usize = 3;
+pub const TORUS_MESH: usize = 4;
+pub const SPHERE_MEDIUM_MESH: usize = 5;
+pub const SPHERE_LOW_MESH: usize = 6;
+
+/// Meshes the main planet can be drawn with, to compare the OBJ with the generators.
+pub const PLANET_MESHES: [(usize, &str); 4] = [
+    (SPHERE_MESH, "sphere.obj"),
+    (UV_SPHERE_MESH, "UV sphere"),
+    (ICOSPHERE_MESH, "icosphere"),
+    (TORUS_MESH, "torus"),
+];
+
+/// Shader numbers of the planets that can be shown with `Scene::planet_system`.
+pub const SELECTABLE_BODIES: [u8; 9] = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+
+/// Shader numbers for the bodies that are not selectable planets.
+pub const MOON_SHADER: u8 = 9;
+pub const RING_SHADER: u8 = 10;
+
+pub struct Body {
+    pub name: String,
+    pub shader: u8,
+    pub mesh: usize,
+    pub scale: f32,
+    pub rotation: Vec3,
+    /// Fixed offset from `parent`, or from the origin, added to the orbit.
+    pub position: Vec3,
+    /// Orbit around `parent`, or around the origin when there is no parent.
+    pub orbit: Option<Orbit>,
+    /// Index of the body this one is attached to.
+    pub parent: Option<usize>,
+    /// Coarser stand-ins for `mesh`, from finest to coarsest.
+    pub lods: Vec<Lod>,
+    /// Material from the mesh's MTL file. When set it replaces the `shader`.
+    pub material: Option<Arc<Material>>,
+    /// Relief added to the mesh in the vertex shader.
+    pub displacement: Option<Displacement>,
+}
+
+impl Body {
+    pub fn new(name: &str, shader: u8, mesh: usize, scale: f32) -> Self {
+        Body {
+            name: name.to_string(),
+            shader,
+            mesh,
+            scale,
+            rotation: Vec3::new(0.0, 0.0, 0.0),
+            position: Vec3::new(0.0, 0.0, 0.0),
+            orbit: None,
+            parent: None,
+            lods: Vec::new(),
+            material: None,
+            displacement: None,
+        }
+    }
+
+    /// Mesh drawn at LOD `level`, where 0 is the body's own mesh.
+    pub fn mesh_for_level(&self, level: usize) -> usize {
+        match level {
+            0 => self.mesh,
+            _ => self.lods.get(level - 1).map_or(self.mesh, |lod| lod.mesh),
+        }
+    }
+
+    /// Rotation at `time`. Orbiting bodies are tidally locked, turning once per revolution.
+    pub fn rotation_at(&self, time: f32) -> Vec3 {
+        match &self.orbit {
+            Some(orbit) => self.rotation + Vec3::new(0.0, orbit.mean_anomaly(time), 0.0),
+            None => self.rotation,
+        }
+    }
+}
+
+/// Directional light. The shaders only see how much light reaches each
+/// fragment, so lights have a strength but no color.
+#[derive(Clone, Copy, Debug)]
+pub struct Light {
+    /// Direction from the scene towards the light, in world space.
+    pub direction: Vec3,
+    pub intensity: f32,
+}
+
+impl Default for Light {
+    /// The light the planets have always had, shining from the starting camera.
+    fn default() -> Self {
+        Light { direction: Vec3::new(0.0, 0.0, 1.0), intensity: 1.0 }
+    }
+}
+
+/// A separately loaded piece of a model file, see `Scene::model`.
+#[derive(Clone)]
+pub struct ModelPart {
+    pub name: String,
+    pub mesh: usize,
+    pub material: Option<Arc<Material>>,
+}
+
+pub struct Scene {
+    pub bodies: Vec<Body>,
+    pub lights: Vec<Light>,
+}
+
+impl Scene {
+    /// The scene shown for a selectable planet: the planet at the origin plus
+    /// Saturn's ring or the Earth's moon.
+    pub fn planet_system(shader_type: u8) -> Self {
+        let mut planet = Body::new(planet_name(shader_type), shader_type, SPHERE_MESH, 1.0);
+        planet.lods = sphere_lods();
+        planet.displacement = match shader_type {
+            5 => Some(terrain::MARS),
+            7 => Some(terrain::MERCURY),
+            _ => None,
+        };
+        let mut bodies = vec![planet];
+
+        match shader_type {
+            2 => {
+                let mut ring = Body::new("Saturn's ring", RING_SHADER, RING_MESH, 0.6);
+                ring.parent = Some(0);
+                bodies.push(ring);
+            }
+            6 => {
+                let mut moon = Body::new("Moon", MOON_SHADER, SPHERE_MESH, 0.3);
+                moon.orbit = Some(Orbit::circular(1.0, 2.0 * PI / 3.0));
+                moon.parent = Some(0);
+                moon.lods = sphere_lods();
+                bodies.push(moon);
+            }
+            _ => {}
+        }
+
+        Scene { bodies, lights: vec![Light::default()] }
+    }
+
+    /// A loaded model at the origin, one body per part. Parts after the first
+    /// are attached to it, so they keep their place in the file's layout.
+    pub fn model(shader_type: u8, parts: &[ModelPart]) -> Self {
+        let bodies = parts.iter().enumerate().map(|(index, part)| {
+            let mut body = Body::new(&part.name, shader_type, part.mesh, 1.0);
+            body.material = part.material.clone();
+            if index > 0 {
+                body.parent = Some(0);
+            }
+            body
+        }).collect();
+        Scene { bodies, lights: vec![Light::default()] }
+    }
+
+    /// World-space position of body `index` at `time`, following parent links.
+    pub fn world_position(&self, index: usize, time: f32) -> Vec3 {
+        let body = &self.bodies[index];
+        let parent_position = match body.parent {
+            Some(parent) => self.world_position(parent, time),
+            None => Vec3::new(0.0, 0.0, 0.0),
+        } + body.position;
+        match &body.orbit {
+            Some(orbit) => parent_position + orbit.position(time),
+            None => parent_position,
+        }
+    }
+
+    /// World-space polyline of body `index`'s orbit at `time`, if it has one.
+    pub fn orbit_path(&self, index: usize, time: f32, segments: usize) -> Option<Vec<Vec3>> {
+        let body = &self.bodies[index];
+        let orbit = body.orbit.as_ref()?;
+        let center = match body.parent {
+            Some(parent) => self.world_position(parent, time),
+            None => Vec3::new(0.0, 0.0, 0.0),
+        } + body.position;
+        Some(orbit.path(segments).into_iter().map(|p| center + p).collect())
+    }
+}
+
+/// Lower resolution UV spheres for bodies drawn with `SPHERE_MESH`.
+pub fn sphere_lods() -> Vec<Lod> {
+    vec![
+        Lod { mesh: SPHERE_MEDIUM_MESH, max_screen_radius: 60.0 },
+        Lod { mesh: SPHERE_LOW_MESH, max_screen_radius: 20.0 },
+    ]
+}
+
+pub fn planet_name(shader_type: u8) -> &'static str {
+    match shader_type {
+        1 => "Jupiter",
+        2 => "Saturn",
+        3 => "Uranus",
+        4 => "Venus",
+        5 => "Mars",
+        6 => "Earth",
+        7 => "Mercury",
+        8 => "Sun",
+        _ => "Neptune",
+    }
+}
@@ -0,0 +1,766 @@
+// scene_file.rs
+//
+// Whole scenes described in a TOML file instead of code. Bodies refer to
+// each other, to meshes and to shaders by name; everything is checked when
+// the file is loaded and errors name the line of the entry at fault.
+//
+//     background = "101020"                # optional, RRGGBB
+//
+//     [camera]                             # optional
+//     eye = [0.0, 4.0, 12.0]
+//     center = [0.0, 0.0, 0.0]
+//     up = [0.0, 1.0, 0.0]                 # optional, defaults to +Y
+//     fov = 45.0                           # optional, degrees
+//
+//     [[light]]                            # optional, one light along +Z by default
+//     direction = [1.0, 0.5, 1.0]          # towards the light
+//     intensity = 1.0                      # optional
+//
+//     [[body]]
+//     name = "Earth"
+//     mesh = "sphere"                      # sphere, uv-sphere, icosphere, torus, ring,
+//                                          # or a model file with a single material,
+//                                          # `file.obj#Part` for one part of any file
+//     shader = "earth"                     # a registered shader, also replacing the
+//                                          # model file's material, or instead:
+//     material = { diffuse = [0.2, 0.4, 0.9], specular = [0.5, 0.5, 0.5], shininess = 32.0 }
+//     terrain = "mars"                     # optional relief: mars or mercury
+//     scale = 1.0                          # optional
+//     position = [0.0, 0.0, 0.0]           # optional, offset from the parent
+//     rotation = [0.0, 0.0, 23.4]          # optional, degrees around X, Y and Z
+//     orbit = { radius = 6.0, period = 40.0, eccentricity = 0.02, inclination = 0.0, phase = 0.0 }
+//     parent = "Sun"                       # optional, the body it moves with
+//     ring = { shader = "ring", scale = 0.6 }   # optional, all fields optional
+//
+//     [[body.moon]]                        # bodies attached to the one above
+//     name = "Moon"
+//     shader = "moon"
+//     scale = 0.3
+//     orbit = { radius = 1.0, period = 3.0 }
+//
+// Model files are found relative to the scene file.
+
+use nalgebra_glm::Vec3;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use toml::Spanned;
+use crate::camera_path::CameraPose;
+use crate::material::Material;
+use crate::model;
+use crate::normals::NormalMode;
+use crate::orbit::Orbit;
+use crate::scene::{self, Body, Light, Scene, RING_SHADER};
+use crate::shaders::ShaderRegistry;
+use crate::terrain;
+use crate::vertex::Vertex;
+use crate::warn_degenerate_triangles;
+
+const DEFAULT_FOV: f32 = 45.0;
+
+/// Built-in meshes by the names scene files use for them.
+const MESH_NAMES: [(&str, usize); 5] = [
+    ("sphere", scene::SPHERE_MESH),
+    ("uv-sphere", scene::UV_SPHERE_MESH),
+    ("icosphere", scene::ICOSPHERE_MESH),
+    ("torus", scene::TORUS_MESH),
+    ("ring", scene::RING_MESH),
+];
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneFileError::Io(err) => write!(f, "could not read scene: {}", err),
+            SceneFileError::Parse(err) => write!(f, "invalid scene file: {}", err),
+            SceneFileError::Invalid(message) => write!(f, "invalid scene: {}", message),
+        }
+    }
+}
+
+/// What a scene file describes besides the scene itself. Camera and
+/// background are left to the caller when the file doesn't give them.
+pub struct SceneFile {
+    pub scene: Scene,
+    pub camera: Option<CameraPose>,
+    pub background: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileEntry {
+    background: Option<Spanned<String>>,
+    camera: Option<Spanned<CameraEntry>>,
+    #[serde(default, rename = "light")]
+    lights: Vec<Spanned<LightEntry>>,
+    #[serde(default, rename = "body")]
+    bodies: Vec<BodyEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraEntry {
+    eye: [f32; 3],
+    center: [f32; 3],
+    up: Option<[f32; 3]>,
+    fov: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LightEntry {
+    direction: [f32; 3],
+    intensity: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BodyEntry {
+    name: Spanned<String>,
+    mesh: Option<Spanned<String>>,
+    shader: Option<Spanned<String>>,
+    material: Option<MaterialEntry>,
+    terrain: Option<Spanned<String>>,
+    scale: Option<f32>,
+    position: Option<[f32; 3]>,
+    rotation: Option<[f32; 3]>,
+    orbit: Option<OrbitEntry>,
+    parent: Option<Spanned<String>>,
+    ring: Option<RingEntry>,
+    #[serde(default, rename = "moon")]
+    moons: Vec<BodyEntry>,
+}
+
+/// Blinn-Phong parameters, like an MTL material. Colors are in the 0-1 range.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialEntry {
+    diffuse: [f32; 3],
+    specular: Option<[f32; 3]>,
+    shininess: Option<f32>,
+    emissive: Option<[f32; 3]>,
+    /// 1 is opaque.
+    opacity: Option<f32>,
+}
+
+/// Angles in degrees.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OrbitEntry {
+    radius: f32,
+    period: f32,
+    eccentricity: Option<f32>,
+    inclination: Option<f32>,
+    phase: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RingEntry {
+    shader: Option<Spanned<String>>,
+    scale: Option<f32>,
+    rotation: Option<[f32; 3]>,
+}
+
+fn vec3(v: [f32; 3]) -> Vec3 {
+    Vec3::new(v[0], v[1], v[2])
+}
+
+fn radians(degrees: [f32; 3]) -> Vec3 {
+    vec3(degrees).map(f32::to_radians)
+}
+
+/// Parses an `RRGGBB` color, with or without a leading `#` or `0x`.
+pub fn parse_color(text: &str) -> Option<u32> {
+    let hex = text.trim_start_matches('#').trim_start_matches("0x");
+    if hex.len() != 6 {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok()
+}
+
+/// Builds scenes out of a parsed file, keeping what is needed to report
+/// errors and to load each model file only once.
+struct Loader<'a> {
+    text: &'a str,
+    directory: &'a Path,
+    meshes: &'a mut Vec<Vec<Vertex>>,
+    shaders: &'a ShaderRegistry,
+    normals: NormalMode,
+    /// Mesh index and material of every model file or part already loaded.
+    models: HashMap<String, (usize, Option<Arc<Material>>)>,
+    bodies: Vec<Body>,
+    /// Body index by name.
+    names: HashMap<String, usize>,
+    /// Parents given by name, resolved once every body is known.
+    parent_names: Vec<(usize, Spanned<String>)>,
+}
+
+impl Loader<'_> {
+    /// 1-based line number of byte `offset`.
+    fn line(&self, offset: usize) -> usize {
+        self.text[..offset.min(self.text.len())].matches('\n').count() + 1
+    }
+
+    /// An error about body `name`, described at byte `offset` of the file.
+    fn error_at(&self, offset: usize, name: &str, message: String) -> SceneFileError {
+        SceneFileError::Invalid(format!("line {}, body \"{}\": {}", self.line(offset), name, message))
+    }
+
+    /// An error about the body whose name is `name`.
+    fn body_error(&self, name: &Spanned<String>, message: String) -> SceneFileError {
+        self.error_at(name.span().start, name.get_ref(), message)
+    }
+
+    fn shader(&self, body: &str, shader: &Spanned<String>) -> Result<u8, SceneFileError> {
+        self.shaders.find(shader.get_ref()).ok_or_else(|| {
+            let names: Vec<&str> = self.shaders.iter().map(|(_, name)| name).collect();
+            let message = format!("unknown shader \"{}\", expected one of {}", shader.get_ref(), names.join(", "));
+            self.error_at(shader.span().start, body, message)
+        })
+    }
+
+    /// Index of the mesh `mesh` refers to, loading model files as needed,
+    /// and the material that comes with it.
+    fn mesh(&mut self, body: &str, mesh: &Spanned<String>) -> Result<(usize, Option<Arc<Material>>), SceneFileError> {
+        let (name, offset) = (mesh.get_ref().as_str(), mesh.span().start);
+        if let Some(&(_, mesh)) = MESH_NAMES.iter().find(|(builtin, _)| *builtin == name) {
+            return Ok((mesh, None));
+        }
+        if let Some(loaded) = self.models.get(name) {
+            return Ok(loaded.clone());
+        }
+
+        let (file, part_name) = match name.split_once('#') {
+            Some((file, part)) => (file, Some(part)),
+            None => (name, None),
+        };
+        if Path::new(file).extension().is_none() {
+            let builtins: Vec<&str> = MESH_NAMES.iter().map(|(builtin, _)| *builtin).collect();
+            return Err(self.error_at(offset, body, format!(
+                "unknown mesh \"{}\", expected one of {} or a model file", name, builtins.join(", "),
+            )));
+        }
+        let filename = self.directory.join(file).to_string_lossy().into_owned();
+        let mut obj = model::load(&filename)
+            .map_err(|err| self.error_at(offset, body, format!("could not load {}: {}", filename, err)))?;
+        obj.generate_normals(self.normals);
+        warn_degenerate_triangles(&filename, &obj);
+
+        let loaded = match part_name {
+            Some(part_name) => {
+                let Some(index) = obj.find_part(part_name) else {
+                    let parts: Vec<String> = obj.parts().into_iter().map(|part| part.name).collect();
+                    return Err(self.error_at(offset, body, format!(
+                        "{} has no part called {} (parts: {})", file, part_name, parts.join(", "),
+                    )));
+                };
+                self.meshes.push(obj.get_part_vertex_array(index));
+                (self.meshes.len() - 1, obj.parts()[index].material.clone())
+            }
+            None => {
+                // A whole file is one body with one material, so its parts must agree on it
+                let mut ids: Vec<Option<usize>> = obj.meshes().iter().map(|mesh| mesh.material).collect();
+                ids.dedup();
+                if ids.len() > 1 {
+                    let parts: Vec<String> = obj.parts().into_iter().map(|part| part.name).collect();
+                    return Err(self.error_at(offset, body, format!(
+                        "{} has parts with different materials, pick one with {}#Part (parts: {})", file, file, parts.join(", "),
+                    )));
+                }
+                let material = ids.first().copied().flatten().and_then(|id| obj.materials().get(id).cloned());
+                self.meshes.push(obj.get_vertex_array());
+                (self.meshes.len() - 1, material)
+            }
+        };
+        self.models.insert(name.to_string(), loaded.clone());
+        Ok(loaded)
+    }
+
+    /// Adds the body described by `entry`, then its ring and its moons.
+    /// Moons are attached to `attached_to`.
+    fn add_body(&mut self, entry: BodyEntry, attached_to: Option<usize>) -> Result<(), SceneFileError> {
+        let name = &entry.name;
+        if self.names.contains_key(name.get_ref()) {
+            return Err(self.body_error(name, "another body already has this name".to_string()));
+        }
+
+        let (mesh, model_material) = match &entry.mesh {
+            Some(mesh) => self.mesh(name.get_ref(), mesh)?,
+            None => (scene::SPHERE_MESH, None),
+        };
+        if entry.shader.is_some() && entry.material.is_some() {
+            return Err(self.body_error(name, "has both a shader and a material, keep only one".to_string()));
+        }
+        let material = match &entry.material {
+            Some(material) => Some(Arc::new(Material {
+                name: name.get_ref().clone(),
+                diffuse: vec3(material.diffuse),
+                specular: material.specular.map_or(Vec3::zeros(), vec3),
+                shininess: material.shininess.unwrap_or(1.0),
+                emissive: material.emissive.map_or(Vec3::zeros(), vec3),
+                dissolve: material.opacity.unwrap_or(1.0),
+                ..Material::default()
+            })),
+            // An explicit shader wins over the model file's material
+            None if entry.shader.is_some() => None,
+            None => model_material,
+        };
+        let shader = match (&entry.shader, &material) {
+            (Some(shader), _) => self.shader(name.get_ref(), shader)?,
+            // The material shader is used instead, whatever the number
+            (None, Some(_)) => 0,
+            (None, None) => return Err(self.body_error(name, "needs a shader or a material".to_string())),
+        };
+
+        let scale = entry.scale.unwrap_or(1.0);
+        if scale <= 0.0 {
+            return Err(self.body_error(name, format!("scale must be positive, not {}", scale)));
+        }
+        let mut body = Body::new(name.get_ref(), shader, mesh, scale);
+        body.material = material;
+        body.position = entry.position.map_or(Vec3::zeros(), vec3);
+        body.rotation = entry.rotation.map_or(Vec3::zeros(), radians);
+        if mesh == scene::SPHERE_MESH {
+            body.lods = scene::sphere_lods();
+        }
+
+        if let Some(terrain) = &entry.terrain {
+            body.displacement = Some(match terrain.get_ref().as_str() {
+                "mars" => terrain::MARS,
+                "mercury" => terrain::MERCURY,
+                other => {
+                    let message = format!("unknown terrain \"{}\", expected mars or mercury", other);
+                    return Err(self.error_at(terrain.span().start, name.get_ref(), message));
+                }
+            });
+        }
+
+        if let Some(orbit) = &entry.orbit {
+            let eccentricity = orbit.eccentricity.unwrap_or(0.0);
+            if orbit.period <= 0.0 {
+                return Err(self.body_error(name, format!("orbit period must be positive, not {}", orbit.period)));
+            }
+            if orbit.radius < 0.0 {
+                return Err(self.body_error(name, format!("orbit radius must not be negative, not {}", orbit.radius)));
+            }
+            if !(0.0..1.0).contains(&eccentricity) {
+                return Err(self.body_error(name, format!("orbit eccentricity must be at least 0 and below 1, not {}", eccentricity)));
+            }
+            body.orbit = Some(Orbit {
+                semi_major_axis: orbit.radius,
+                eccentricity,
+                inclination: orbit.inclination.unwrap_or(0.0).to_radians(),
+                period: orbit.period,
+                phase: orbit.phase.unwrap_or(0.0).to_radians(),
+            });
+        }
+
+        let index = self.bodies.len();
+        match (&entry.parent, attached_to) {
+            (Some(_), Some(moon_of)) => {
+                let message = format!("is a moon of \"{}\" and can't have a parent of its own", self.bodies[moon_of].name);
+                return Err(self.body_error(name, message));
+            }
+            (Some(parent), None) => self.parent_names.push((index, parent.clone())),
+            (None, moon_of) => body.parent = moon_of,
+        }
+        self.names.insert(name.get_ref().clone(), index);
+        self.bodies.push(body);
+
+        if let Some(ring) = &entry.ring {
+            let shader = match &ring.shader {
+                Some(shader) => self.shader(name.get_ref(), shader)?,
+                None => RING_SHADER,
+            };
+            let scale = ring.scale.unwrap_or(0.6);
+            if scale <= 0.0 {
+                return Err(self.body_error(name, format!("ring scale must be positive, not {}", scale)));
+            }
+            let ring_name = format!("{}'s ring", name.get_ref());
+            if self.names.contains_key(&ring_name) {
+                return Err(self.body_error(name, format!("its ring would be called \"{}\", but another body already has this name", ring_name)));
+            }
+            let mut ring_body = Body::new(&ring_name, shader, scene::RING_MESH, scale);
+            ring_body.rotation = ring.rotation.map_or(Vec3::zeros(), radians);
+            ring_body.parent = Some(index);
+            self.names.insert(ring_name, self.bodies.len());
+            self.bodies.push(ring_body);
+        }
+
+        for moon in entry.moons {
+            self.add_body(moon, Some(index))?;
+        }
+        Ok(())
+    }
+
+    /// Fills in the parents given by name and makes sure no body ends up
+    /// being its own ancestor.
+    fn resolve_parents(&mut self) -> Result<(), SceneFileError> {
+        let parent_names = std::mem::take(&mut self.parent_names);
+        for (index, parent) in &parent_names {
+            let index = *index;
+            let (offset, name) = (parent.span().start, &self.bodies[index].name);
+            let Some(&parent_index) = self.names.get(parent.get_ref()) else {
+                return Err(self.error_at(offset, name, format!("parent \"{}\" is not a body in this scene", parent.get_ref())));
+            };
+            if parent_index == index {
+                return Err(self.error_at(offset, name, "can't be its own parent".to_string()));
+            }
+            self.bodies[index].parent = Some(parent_index);
+        }
+
+        for start in 0..self.bodies.len() {
+            let mut current = start;
+            for _ in 0..self.bodies.len() {
+                match self.bodies[current].parent {
+                    Some(parent) => current = parent,
+                    None => break,
+                }
+            }
+            if self.bodies[current].parent.is_some() {
+                // `current` is on the loop. Moons and rings always point to an
+                // earlier body, so some link in it was given by name; report that one
+                let (culprit, parent) = loop {
+                    if let Some((index, parent)) = parent_names.iter().find(|(index, _)| *index == current) {
+                        break (*index, parent);
+                    }
+                    current = self.bodies[current].parent.expect("bodies on a loop have parents");
+                };
+                let message = format!("parent \"{}\" leads back to this body", parent.get_ref());
+                return Err(self.error_at(parent.span().start, &self.bodies[culprit].name, message));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Loads the scene file `filename`. Meshes from model files are appended to
+/// `meshes` and shaders are looked up by name in `shaders`.
+pub fn load(filename: &str, meshes: &mut Vec<Vec<Vertex>>, shaders: &ShaderRegistry, normals: NormalMode) -> Result<SceneFile, SceneFileError> {
+    let text = std::fs::read_to_string(filename).map_err(SceneFileError::Io)?;
+    let directory = Path::new(filename).parent().unwrap_or(Path::new("."));
+    parse(&text, directory, meshes, shaders, normals)
+}
+
+/// Builds a scene from the text of a scene file, with model files found relative to `directory`.
+pub fn parse(text: &str, directory: &Path, meshes: &mut Vec<Vec<Vertex>>, shaders: &ShaderRegistry, normals: NormalMode) -> Result<SceneFile, SceneFileError> {
+    let file: FileEntry = toml::from_str(text).map_err(SceneFileError::Parse)?;
+    let mut loader = Loader {
+        text,
+        directory,
+        meshes,
+        shaders,
+        normals,
+        models: HashMap::new(),
+        bodies: Vec::new(),
+        names: HashMap::new(),
+        parent_names: Vec::new(),
+    };
+
+    let background = match &file.background {
+        Some(color) => Some(parse_color(color.get_ref()).ok_or_else(|| SceneFileError::Invalid(format!(
+            "line {}: background \"{}\" is not an RRGGBB color", loader.line(color.span().start), color.get_ref(),
+        )))?),
+        None => None,
+    };
+
+    let camera = match &file.camera {
+        Some(entry) => {
+            let line = loader.line(entry.span().start);
+            let entry = entry.get_ref();
+            let pose = CameraPose {
+                eye: vec3(entry.eye),
+                center: vec3(entry.center),
+                up: entry.up.map_or(Vec3::new(0.0, 1.0, 0.0), vec3),
+                fov: entry.fov.unwrap_or(DEFAULT_FOV),
+            };
+            if (pose.center - pose.eye).magnitude() < f32::EPSILON {
+                return Err(SceneFileError::Invalid(format!("line {}, camera: eye and center are the same point", line)));
+            }
+            if !(pose.fov > 0.0 && pose.fov < 180.0) {
+                return Err(SceneFileError::Invalid(format!("line {}, camera: fov must be between 0 and 180 degrees, not {}", line, pose.fov)));
+            }
+            Some(pose)
+        }
+        None => None,
+    };
+
+    let mut lights = Vec::new();
+    for (index, entry) in file.lights.iter().enumerate() {
+        let line = loader.line(entry.span().start);
+        let direction = vec3(entry.get_ref().direction);
+        if direction.magnitude() < f32::EPSILON {
+            return Err(SceneFileError::Invalid(format!("line {}, light {}: direction must not be zero", line, index + 1)));
+        }
+        lights.push(Light {
+            direction: direction.normalize(),
+            intensity: entry.get_ref().intensity.unwrap_or(1.0),
+        });
+    }
+    if lights.is_empty() {
+        lights.push(Light::default());
+    }
+
+    if file.bodies.is_empty() {
+        return Err(SceneFileError::Invalid("at least one [[body]] is required".to_string()));
+    }
+    for entry in file.bodies {
+        loader.add_body(entry, None)?;
+    }
+    loader.resolve_parents()?;
+
+    Ok(SceneFile {
+        scene: Scene { bodies: loader.bodies, lights },
+        camera,
+        background,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_text(text: &str) -> Result<SceneFile, SceneFileError> {
+        let models = Path::new(env!("CARGO_MANIFEST_DIR")).join("models");
+        parse(text, &models, &mut Vec::new(), &ShaderRegistry::builtin(), NormalMode::FromFile)
+    }
+
+    fn load_ok(text: &str) -> SceneFile {
+        match load_text(text) {
+            Ok(file) => file,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    fn error(text: &str) -> String {
+        match load_text(text) {
+            Err(SceneFileError::Invalid(message)) => message,
+            Err(other) => panic!("expected an invalid scene, got {}", other),
+            Ok(_) => panic!("expected an invalid scene, but it loaded"),
+        }
+    }
+
+    #[test]
+    fn rejects_parent_cycles() {
+        let text = "\
+[[body]]
+name = \"A\"
+shader = \"moon\"
+parent = \"B\"
+
+[[body]]
+name = \"B\"
+shader = \"moon\"
+parent = \"A\"
+";
+        assert_eq!(
+            error(text),
+            "line 4, body \"A\": parent \"B\" leads back to this body",
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_parents() {
+        let text = "\
+[[body]]
+name = \"A\"
+shader = \"moon\"
+
+[[body]]
+name = \"B\"
+shader = \"moon\"
+parent = \"C\"
+";
+        assert_eq!(
+            error(text),
+            "line 8, body \"B\": parent \"C\" is not a body in this scene",
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_names() {
+        let text = "\
+[[body]]
+name = \"A\"
+shader = \"moon\"
+
+[[body]]
+name = \"A\"
+shader = \"moon\"
+";
+        assert_eq!(
+            error(text),
+            "line 6, body \"A\": another body already has this name",
+        );
+    }
+
+    #[test]
+    fn rejects_bad_colors() {
+        let text = "\
+# night sky
+background = \"blue\"
+
+[[body]]
+name = \"A\"
+shader = \"moon\"
+";
+        assert_eq!(
+            error(text),
+            "line 2: background \"blue\" is not an RRGGBB color",
+        );
+    }
+
+    #[test]
+    fn rejects_open_orbits() {
+        let text = "\
+[[body]]
+name = \"Sun\"
+shader = \"moon\"
+
+[[body]]
+name = \"Comet\"
+shader = \"moon\"
+orbit = { radius = 3.0, period = 10.0, eccentricity = 1.0 }
+";
+        assert_eq!(
+            error(text),
+            "line 6, body \"Comet\": orbit eccentricity must be at least 0 and below 1, not 1",
+        );
+    }
+
+    #[test]
+    fn links_moons_and_rings_to_their_planet() {
+        let text = "\
+[[body]]
+name = \"Sun\"
+shader = \"sun\"
+
+[[body]]
+name = \"Saturn\"
+shader = \"saturn\"
+parent = \"Sun\"
+orbit = { radius = 6.0, period = 40.0 }
+ring = { scale = 0.6 }
+
+[[body.moon]]
+name = \"Titan\"
+shader = \"moon\"
+orbit = { radius = 1.5, period = 4.0 }
+";
+        let file = load_ok(text);
+        let bodies: Vec<(&str, Option<usize>)> = file.scene.bodies.iter()
+            .map(|body| (body.name.as_str(), body.parent))
+            .collect();
+        assert_eq!(bodies, [
+            ("Sun", None),
+            ("Saturn", Some(0)),
+            ("Saturn's ring", Some(1)),
+            ("Titan", Some(1)),
+        ]);
+    }
+
+    #[test]
+    fn rejects_a_shader_together_with_a_material() {
+        let text = "\
+[[body]]
+name = \"A\"
+shader = \"moon\"
+material = { diffuse = [1.0, 0.0, 0.0] }
+";
+        assert_eq!(
+            error(text),
+            "line 2, body \"A\": has both a shader and a material, keep only one",
+        );
+    }
+
+    #[test]
+    fn a_shader_replaces_the_model_material() {
+        let text = "\
+[[body]]
+name = \"Plain\"
+mesh = \"satellite.obj#Body\"
+shader = \"moon\"
+
+[[body]]
+name = \"Gold\"
+mesh = \"satellite.obj#Body\"
+";
+        let file = load_ok(text);
+        let shaders = ShaderRegistry::builtin();
+        assert!(file.scene.bodies[0].material.is_none());
+        assert_eq!(Some(file.scene.bodies[0].shader), shaders.find("moon"));
+        let material = file.scene.bodies[1].material.as_ref().expect("the part's MTL material");
+        assert_eq!(material.name, "gold");
+    }
+
+    #[test]
+    fn rejects_whole_models_with_several_materials() {
+        let text = "\
+[[body]]
+name = \"Probe\"
+mesh = \"satellite.obj\"
+";
+        assert_eq!(
+            error(text),
+            "line 3, body \"Probe\": satellite.obj has parts with different materials, \
+             pick one with satellite.obj#Part (parts: Body, PanelLeft, PanelRight, Beacon)",
+        );
+    }
+
+    #[test]
+    fn ring_names_are_taken_like_body_names() {
+        let text = "\
+[[body]]
+name = \"Saturn\"
+shader = \"saturn\"
+ring = {}
+
+[[body]]
+name = \"Saturn's ring\"
+shader = \"moon\"
+";
+        assert_eq!(error(text), "line 7, body \"Saturn's ring\": another body already has this name");
+
+        let text = "\
+[[body]]
+name = \"Saturn's ring\"
+shader = \"moon\"
+
+[[body]]
+name = \"Saturn\"
+shader = \"saturn\"
+ring = {}
+";
+        assert_eq!(
+            error(text),
+            "line 6, body \"Saturn\": its ring would be called \"Saturn's ring\", but another body already has this name",
+        );
+    }
+
+    #[test]
+    fn rings_can_be_parents() {
+        let text = "\
+[[body]]
+name = \"Saturn\"
+shader = \"saturn\"
+ring = {}
+
+[[body]]
+name = \"Shepherd\"
+shader = \"moon\"
+parent = \"Saturn's ring\"
+";
+        let file = load_ok(text);
+        assert_eq!(file.scene.bodies[2].name, "Shepherd");
+        assert_eq!(file.scene.bodies[2].parent, Some(1));
+    }
+}
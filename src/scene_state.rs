@@ -0,0 +1,105 @@
+// scene_state.rs
+//
+// A scene together with what is kept for it from frame to frame: the orbit
+// trails and the level of detail of every body. Built-in planet systems can
+// be switched to another planet or mesh; scenes from a file stay as loaded.
+
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::lod::LodState;
+use crate::orbit::Trail;
+use crate::scene::{self, ModelPart, Scene, SPHERE_MESH};
+use crate::shaders::ShaderRegistry;
+use crate::timing::FrameStats;
+use crate::vertex::Vertex;
+use crate::{lod_report, render_orbits, render_scene, update_trails, TRAIL_LENGTH, TRAIL_SPACING};
+
+/// What the scene was built from, which decides what can be switched.
+enum Source {
+    Planets,
+    /// A loaded model standing in for the planet.
+    Model(Vec<ModelPart>),
+    File,
+}
+
+pub struct SceneState {
+    pub scene: Scene,
+    /// One per body.
+    pub trails: Vec<Trail>,
+    /// One per body.
+    pub lod_states: Vec<LodState>,
+    source: Source,
+}
+
+impl SceneState {
+    /// The planet system of `shader_type`, with the parts of `model` in place
+    /// of the planet when one is given.
+    pub fn planets(shader_type: u8, model: Option<Vec<ModelPart>>) -> Self {
+        let source = match model {
+            Some(parts) => Source::Model(parts),
+            None => Source::Planets,
+        };
+        Self::new(build(&source, shader_type), source)
+    }
+
+    /// A scene loaded from a file, which can't be switched to another planet.
+    pub fn fixed(scene: Scene) -> Self {
+        Self::new(scene, Source::File)
+    }
+
+    fn new(scene: Scene, source: Source) -> Self {
+        let mut state = SceneState { scene, trails: Vec::new(), lod_states: Vec::new(), source };
+        state.reset();
+        state
+    }
+
+    /// Starts every trail and LOD over, for a scene with new bodies.
+    fn reset(&mut self) {
+        self.trails = self.scene.bodies.iter().map(|_| Trail::new(TRAIL_LENGTH, TRAIL_SPACING)).collect();
+        self.lod_states = vec![LodState::default(); self.scene.bodies.len()];
+    }
+
+    /// Rebuilds the scene around planet `shader_type`. Returns whether the
+    /// scene changed, which never happens for scene files.
+    pub fn select_body(&mut self, shader_type: u8) -> bool {
+        if matches!(self.source, Source::File) || self.scene.bodies[0].shader == shader_type {
+            return false;
+        }
+        self.scene = build(&self.source, shader_type);
+        self.reset();
+        true
+    }
+
+    /// Draws the planet with mesh `mesh`. Only the built-in planet systems
+    /// can change their planet's mesh.
+    pub fn set_planet_mesh(&mut self, mesh: usize) {
+        let planet = &mut self.scene.bodies[0];
+        if !matches!(self.source, Source::Planets) || planet.mesh == mesh {
+            return;
+        }
+        planet.mesh = mesh;
+        // The sphere LODs only stand in for the sphere
+        planet.lods = if mesh == SPHERE_MESH { scene::sphere_lods() } else { Vec::new() };
+    }
+
+    /// Draws every body and records where the orbiting ones are for their trails.
+    pub fn render(&mut self, framebuffer: &mut Framebuffer, meshes: &[Vec<Vertex>], shaders: &ShaderRegistry, camera: &Camera, sim_time: f32, stats: &mut FrameStats) {
+        render_scene(framebuffer, &self.scene, meshes, shaders, &mut self.lod_states, camera, sim_time, stats);
+        update_trails(&mut self.trails, &self.scene, sim_time);
+    }
+
+    pub fn render_orbits(&self, framebuffer: &mut Framebuffer, camera: &Camera, sim_time: f32, antialiased: bool) {
+        render_orbits(framebuffer, &self.scene, &self.trails, camera, sim_time, antialiased);
+    }
+
+    pub fn lod_report(&self, meshes: &[Vec<Vertex>]) -> String {
+        lod_report(&self.scene, meshes, &self.lod_states)
+    }
+}
+
+fn build(source: &Source, shader_type: u8) -> Scene {
+    match source {
+        Source::Model(parts) => Scene::model(shader_type, parts),
+        _ => Scene::planet_system(shader_type),
+    }
+}
@@ -0,0 +1,86 @@
+// screenshot.rs
+//
+// Saves the rendered frame, without the HUD, as a timestamped PNG in
+// `screenshots/`, optionally with its depth buffer next to it.
+
+use image::{ImageBuffer, ImageError, ImageResult, Luma};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::framebuffer::Framebuffer;
+use crate::headless::save_png;
+
+const SCREENSHOT_DIR: &str = "screenshots";
+
+/// Current UTC time as `YYYY-MM-DD_HH-MM-SS`, safe to use in file names.
+fn timestamp() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+    let (days, time_of_day) = (seconds / 86400, seconds % 86400);
+
+    // Civil date from days since 1970-01-01, after Howard Hinnant's days_from_civil inverse
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year, month, day, time_of_day / 3600, time_of_day / 60 % 60, time_of_day % 60
+    )
+}
+
+/// Writes the depth buffer as a 16-bit grayscale PNG. Depths are stretched
+/// over the range actually covered, nearest black and farthest white, with
+/// the empty background white as well. Returns that range, if anything was drawn.
+pub fn save_depth_png(framebuffer: &Framebuffer, filename: &str) -> ImageResult<Option<(f32, f32)>> {
+    let drawn = framebuffer.zbuffer.iter().copied().filter(|depth| depth.is_finite());
+    let range = drawn.fold(None, |range: Option<(f32, f32)>, depth| match range {
+        Some((near, far)) => Some((near.min(depth), far.max(depth))),
+        None => Some((depth, depth)),
+    });
+
+    let pixels = framebuffer.zbuffer.iter().map(|&depth| match range {
+        Some((near, far)) if depth.is_finite() && far > near => {
+            ((depth - near) / (far - near) * u16::MAX as f32).round() as u16
+        }
+        Some(_) if depth.is_finite() => 0,
+        _ => u16::MAX,
+    }).collect();
+
+    let image: ImageBuffer<Luma<u16>, Vec<u16>> =
+        ImageBuffer::from_raw(framebuffer.width as u32, framebuffer.height as u32, pixels)
+            .expect("the depth buffer holds one value per pixel");
+    image.save(filename)?;
+    Ok(range)
+}
+
+/// Saves `framebuffer` as `screenshots/screenshot_<time>.png`, plus
+/// `..._depth.png` when `with_depth` is set, and prints where they went.
+pub fn take(framebuffer: &Framebuffer, with_depth: bool) -> ImageResult<()> {
+    std::fs::create_dir_all(SCREENSHOT_DIR).map_err(ImageError::IoError)?;
+
+    // Two shots in the same second get a counter instead of overwriting each other
+    let stamp = timestamp();
+    let mut base = format!("{}/screenshot_{}", SCREENSHOT_DIR, stamp);
+    let mut copy = 1;
+    while Path::new(&format!("{}.png", base)).exists() {
+        copy += 1;
+        base = format!("{}/screenshot_{}_{}", SCREENSHOT_DIR, stamp, copy);
+    }
+
+    let filename = format!("{}.png", base);
+    save_png(framebuffer, &filename)?;
+    println!("Saved {}", filename);
+    if with_depth {
+        let depth_filename = format!("{}_depth.png", base);
+        match save_depth_png(framebuffer, &depth_filename)? {
+            Some((near, far)) => println!("Saved {} (depth {:.4} to {:.4})", depth_filename, near, far),
+            None => println!("Saved {}", depth_filename),
+        }
+    }
+    Ok(())
+}
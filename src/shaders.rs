@@ -4,12 +4,20 @@ use crate::Uniforms;
 use crate::color::Color;
 use crate::fragment::Fragment;
 use std::f32::consts::PI;
+use std::collections::BTreeMap;
+use crate::scene::Body;
 
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
+	// Terrain relief moves the vertex before it is transformed
+	let (object_position, object_normal) = match &uniforms.displacement {
+		Some(displacement) => displacement.displace(&vertex.position, &vertex.normal),
+		None => (vertex.position, vertex.normal),
+	};
+
 	let position = Vec4::new(
-		vertex.position.x,
-		vertex.position.y,
-		vertex.position.z,
+		object_position.x,
+		object_position.y,
+		object_position.z,
 		1.0
 	);
 	let transformed =  uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix * position;
@@ -27,15 +35,15 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
 	let model_mat3 = mat4_to_mat3(&uniforms.model_matrix);
 	let normal_matrix = model_mat3.transpose().try_inverse().unwrap_or(Mat3::identity());
 
-	let transformed_normal = normal_matrix * vertex.normal;
+	let transformed_normal = normal_matrix * object_normal;
 
 	Vertex {
-		position: vertex.position,
-		normal: vertex.normal,
+		position: object_position,
+		normal: object_normal,
 		tex_coords: vertex.tex_coords,
 		color: vertex.color,
 		transformed_position: Vec3::new(screen_position.x, screen_position.y, screen_position.z),
-		transformed_normal: vertex.normal,
+		transformed_normal: object_normal,
 	}
 }
 
@@ -135,7 +143,7 @@ pub fn fragment_shader_saturn_with_ring(fragment: &Fragment, uniforms: &Uniforms
     saturn_color * fragment.intensity
 }
 
-fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
     let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0); // Clamp x between edge0 and edge1
     t * t * (3.0 - 2.0 * t) // Smooth interpolation
 }
@@ -297,3 +305,104 @@ pub fn fragment_shader_ring(fragment: &Fragment, _uniforms: &Uniforms) -> Color
 
 
 
+
+
+/// Blinn-Phong shading from the body's MTL material, used instead of the
+/// planet shaders when the model brings its own materials.
+pub fn fragment_shader_material(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let Some(material) = &uniforms.material else {
+        return Color::new(255.0, 0.0, 255.0);
+    };
+
+    let mut diffuse = Color::new(material.diffuse.x * 255.0, material.diffuse.y * 255.0, material.diffuse.z * 255.0);
+    if let Some(texture) = &material.diffuse_texture {
+        let texel = texture.sample(&fragment.tex_coords);
+        diffuse = Color::new(diffuse.r * texel.r / 255.0, diffuse.g * texel.g / 255.0, diffuse.b * texel.b / 255.0);
+    }
+
+    // Blinn-Phong highlight of every light that reaches the front of the surface
+    let ambient = 0.1;
+    let view = (uniforms.camera_position - fragment.vertex_position).normalize();
+    let specular: f32 = uniforms.lights.iter()
+        .filter(|light| dot(&fragment.normal, &light.direction) > 0.0)
+        .map(|light| {
+            let half = (light.direction + view).normalize();
+            dot(&fragment.normal, &half).max(0.0).powf(material.shininess.max(1.0)) * light.intensity
+        })
+        .sum();
+    let emissive = material.emissive * 255.0;
+    let specular_color = material.specular * (specular * 255.0);
+
+    let color = diffuse * (ambient + fragment.intensity)
+        + Color::new(emissive.x, emissive.y, emissive.z)
+        + Color::new(specular_color.x, specular_color.y, specular_color.z);
+    color.clamp()
+}
+
+/// Signature every fragment shader has, so shaders can be stored and swapped.
+pub type FragmentShader = fn(&Fragment, &Uniforms) -> Color;
+
+/// Fragment shaders by the shader number bodies refer to, each with a short
+/// name. Tools can register their own shaders or replace the built-in ones.
+pub struct ShaderRegistry {
+    shaders: BTreeMap<u8, (String, FragmentShader)>,
+}
+
+impl ShaderRegistry {
+    /// A registry with no shaders in it.
+    pub fn empty() -> Self {
+        ShaderRegistry { shaders: BTreeMap::new() }
+    }
+
+    /// The planet, moon and ring shaders under the numbers the scenes use.
+    pub fn builtin() -> Self {
+        let mut registry = ShaderRegistry::empty();
+        registry.register(0, "neptune", fragment_shader_neptune);
+        registry.register(1, "jupiter", fragment_shader_jupiter);
+        registry.register(2, "saturn", fragment_shader_saturn_with_ring);
+        registry.register(3, "uranus", fragment_shader_urano);
+        registry.register(4, "venus", fragment_shader_venus);
+        registry.register(5, "mars", fragment_shader_mars);
+        registry.register(6, "earth", fragment_shader_earth);
+        registry.register(7, "mercury", fragment_shader_mercury);
+        registry.register(8, "sun", fragment_shader_sun);
+        registry.register(9, "moon", fragment_shader_moon);
+        registry.register(10, "ring", fragment_shader_ring);
+        registry
+    }
+
+    /// Adds `shader` as number `id`, replacing whatever had that number.
+    pub fn register(&mut self, id: u8, name: &str, shader: FragmentShader) {
+        self.shaders.insert(id, (name.to_string(), shader));
+    }
+
+    pub fn get(&self, id: u8) -> Option<FragmentShader> {
+        self.shaders.get(&id).map(|(_, shader)| *shader)
+    }
+
+    /// Number of the shader registered as `name`, ignoring case.
+    pub fn find(&self, name: &str) -> Option<u8> {
+        self.shaders.iter().find(|(_, (registered, _))| registered.eq_ignore_ascii_case(name)).map(|(id, _)| *id)
+    }
+
+    /// Numbers and names of every registered shader, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &str)> {
+        self.shaders.iter().map(|(id, (name, _))| (*id, name.as_str()))
+    }
+
+    /// Shader `body` is drawn with: its material's when it has one, otherwise
+    /// its registered shader, with Neptune's standing in for unknown numbers
+    /// as it always has.
+    pub fn for_body(&self, body: &Body) -> FragmentShader {
+        if body.material.is_some() {
+            return fragment_shader_material;
+        }
+        self.get(body.shader).unwrap_or(fragment_shader_neptune)
+    }
+}
+
+impl Default for ShaderRegistry {
+    fn default() -> Self {
+        ShaderRegistry::builtin()
+    }
+}
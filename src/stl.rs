@@ -0,0 +1,178 @@
+// stl.rs
+//
+// Reader for ASCII and binary STL files. STL stores every triangle with its
+// own three corners and a facet normal, so the mesh comes out unwelded and
+// flat shaded unless normals are regenerated with `--normals smooth`.
+
+use nalgebra_glm::Vec3;
+use crate::model::ModelError;
+use crate::obj::{Obj, ObjMesh};
+
+const HEADER_SIZE: usize = 80;
+const TRIANGLE_SIZE: usize = 50;
+
+fn invalid(message: &str) -> ModelError {
+    ModelError::Invalid(format!("STL: {}", message))
+}
+
+/// Binary files may also start with "solid", so the size has to match too.
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() < HEADER_SIZE + 4 {
+        return false;
+    }
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    bytes.len() == HEADER_SIZE + 4 + count * TRIANGLE_SIZE || !bytes.starts_with(b"solid")
+}
+
+fn read_vec3(bytes: &[u8]) -> Vec3 {
+    let float = |i: usize| f32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+    Vec3::new(float(0), float(4), float(8))
+}
+
+/// Facet normals followed by the corners of every triangle.
+fn parse_binary(bytes: &[u8]) -> Result<(Vec<Vec3>, Vec<Vec3>), ModelError> {
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    let body = &bytes[HEADER_SIZE + 4..];
+    if body.len() < count * TRIANGLE_SIZE {
+        return Err(invalid(&format!("the header promises {} triangles but the file ends early", count)));
+    }
+
+    let mut normals = Vec::with_capacity(count);
+    let mut corners = Vec::with_capacity(count * 3);
+    for triangle in body.chunks_exact(TRIANGLE_SIZE).take(count) {
+        normals.push(read_vec3(&triangle[0..12]));
+        for corner in 0..3 {
+            corners.push(read_vec3(&triangle[12 + corner * 12..24 + corner * 12]));
+        }
+    }
+    Ok((normals, corners))
+}
+
+fn parse_ascii(text: &str) -> Result<(Vec<Vec3>, Vec<Vec3>), ModelError> {
+    let mut normals = Vec::new();
+    let mut corners = Vec::new();
+    let parse_vec3 = |words: &[&str], line: usize| -> Result<Vec3, ModelError> {
+        let values: Vec<f32> = words.iter().filter_map(|word| word.parse().ok()).collect();
+        match values[..] {
+            [x, y, z] => Ok(Vec3::new(x, y, z)),
+            _ => Err(invalid(&format!("line {}: expected three numbers", line))),
+        }
+    };
+
+    for (index, line) in text.lines().enumerate() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["facet", "normal", rest @ ..] => normals.push(parse_vec3(rest, index + 1)?),
+            ["vertex", rest @ ..] => corners.push(parse_vec3(rest, index + 1)?),
+            _ => {}
+        }
+    }
+
+    if corners.len() != normals.len() * 3 {
+        return Err(invalid(&format!("{} facets but {} vertices", normals.len(), corners.len())));
+    }
+    Ok((normals, corners))
+}
+
+pub fn load(filename: &str) -> Result<Obj, ModelError> {
+    let bytes = std::fs::read(filename).map_err(ModelError::Io)?;
+    let name = std::path::Path::new(filename)
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    parse(&bytes, name)
+}
+
+/// Reads a whole STL file held in memory as a single part called `name`.
+fn parse(bytes: &[u8], name: String) -> Result<Obj, ModelError> {
+    let (facet_normals, vertices) = if is_binary(bytes) {
+        parse_binary(bytes)?
+    } else {
+        let text = std::str::from_utf8(bytes).map_err(|_| invalid("the file is neither binary nor text"))?;
+        parse_ascii(text)?
+    };
+
+    // Many exporters leave the facet normals at zero; let Obj generate them then
+    let normals = if facet_normals.iter().all(|normal| normal.magnitude() > 0.0) {
+        facet_normals.iter().flat_map(|normal| [normal.normalize(); 3]).collect()
+    } else {
+        Vec::new()
+    };
+
+    let indices = (0..vertices.len() as u32).collect();
+    let mesh = ObjMesh { name, vertices, normals, texcoords: Vec::new(), colors: Vec::new(), indices, material: None };
+    Ok(Obj::from_meshes(vec![mesh], Vec::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII: &str = "\
+solid triangle
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid triangle
+";
+
+    /// A binary file with one triangle, whose 80-byte header starts with `header`.
+    fn binary(header: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0u8; HEADER_SIZE];
+        bytes[..header.len()].copy_from_slice(header);
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        for value in [0.0f32, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0, 0]);
+        bytes
+    }
+
+    fn check_triangle(obj: &Obj) {
+        let mesh = &obj.meshes()[0];
+        assert_eq!(mesh.vertices, [Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)]);
+        assert_eq!(mesh.normals, [Vec3::new(0.0, 0.0, 1.0); 3]);
+        assert_eq!(mesh.indices, [0, 1, 2]);
+    }
+
+    #[test]
+    fn reads_ascii_files() {
+        assert!(!is_binary(ASCII.as_bytes()));
+        check_triangle(&parse(ASCII.as_bytes(), "triangle".to_string()).expect("the ASCII triangle loads"));
+    }
+
+    #[test]
+    fn reads_binary_files() {
+        let bytes = binary(b"exported by a CAD tool");
+        assert!(is_binary(&bytes));
+        check_triangle(&parse(&bytes, "triangle".to_string()).expect("the binary triangle loads"));
+    }
+
+    #[test]
+    fn binary_files_may_start_with_solid() {
+        // The size matches one triangle exactly, so the "solid" header doesn't make it ASCII
+        let bytes = binary(b"solid but binary");
+        assert!(is_binary(&bytes));
+        check_triangle(&parse(&bytes, "triangle".to_string()).expect("the binary triangle loads"));
+    }
+
+    #[test]
+    fn short_ascii_files_are_not_binary() {
+        assert!(!is_binary(b"solid empty\nendsolid empty\n"));
+    }
+
+    #[test]
+    fn rejects_truncated_binary_files() {
+        let bytes = binary(b"exported by a CAD tool");
+        match parse(&bytes[..bytes.len() - 10], "triangle".to_string()) {
+            Err(ModelError::Invalid(message)) => {
+                assert_eq!(message, "STL: the header promises 1 triangles but the file ends early");
+            }
+            Err(other) => panic!("expected an invalid model, got {}", other),
+            Ok(_) => panic!("expected an invalid model, but it loaded"),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+// terrain.rs
+//
+// Height fields for rocky planets, applied in the vertex shader by moving
+// each vertex along its normal. The height is a function of the object-space
+// position, so vertices shared between triangles move together as long as
+// they also share a normal (smooth normals, not `--normals flat`).
+
+use nalgebra_glm::Vec3;
+use crate::shaders::smoothstep;
+
+/// Relief of a body: fractal noise for mountains and valleys plus a field of
+/// randomly placed craters. Heights are in object units; our spheres have a
+/// radius of 0.5.
+#[derive(Clone, Copy, Debug)]
+pub struct Displacement {
+    /// Height of the noise at its peaks.
+    pub amplitude: f32,
+    /// Features of the first noise octave per object unit.
+    pub frequency: f32,
+    pub octaves: u32,
+    /// Crater cells per object unit; each cell holds at most one crater.
+    pub crater_frequency: f32,
+    /// Depth of the largest craters, 0 for none.
+    pub crater_depth: f32,
+    pub seed: u32,
+}
+
+/// Tall mountains and canyons with a few craters.
+pub const MARS: Displacement = Displacement {
+    amplitude: 0.035,
+    frequency: 3.0,
+    octaves: 5,
+    crater_frequency: 4.0,
+    crater_depth: 0.02,
+    seed: 4,
+};
+
+/// Mostly flat, covered in craters.
+pub const MERCURY: Displacement = Displacement {
+    amplitude: 0.008,
+    frequency: 4.0,
+    octaves: 4,
+    crater_frequency: 7.0,
+    crater_depth: 0.03,
+    seed: 1,
+};
+
+/// Step used to find the slope of the displaced surface around a vertex.
+const NORMAL_EPSILON: f32 = 0.002;
+
+impl Displacement {
+    /// Height of the surface above (or below) `position`.
+    pub fn height(&self, position: &Vec3) -> f32 {
+        self.amplitude * fbm(&(position * self.frequency), self.octaves, self.seed)
+            + self.crater_depth * craters(&(position * self.crater_frequency), self.seed)
+    }
+
+    /// Moves `position` along `normal` by the height there and returns it with
+    /// the normal of the displaced surface, found from two nearby points
+    /// displaced the same way.
+    pub fn displace(&self, position: &Vec3, normal: &Vec3) -> (Vec3, Vec3) {
+        let normal = normal.normalize();
+        let displaced = |point: Vec3| point + normal * self.height(&point);
+
+        // Any vector not parallel to the normal gives a tangent frame
+        let helper = if normal.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+        let tangent = normal.cross(&helper).normalize();
+        let bitangent = normal.cross(&tangent);
+
+        let center = displaced(*position);
+        let along_tangent = displaced(position + tangent * NORMAL_EPSILON) - center;
+        let along_bitangent = displaced(position + bitangent * NORMAL_EPSILON) - center;
+        let new_normal = along_tangent.cross(&along_bitangent).normalize();
+
+        if new_normal.iter().all(|c| c.is_finite()) {
+            (center, new_normal)
+        } else {
+            (center, normal)
+        }
+    }
+}
+
+fn hash(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+    let mut h = seed
+        ^ (x as u32).wrapping_mul(0x8da6b343)
+        ^ (y as u32).wrapping_mul(0xd8163841)
+        ^ (z as u32).wrapping_mul(0xcb1ab31f);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1e995);
+    h ^ (h >> 15)
+}
+
+/// Pseudo-random value in [0, 1] for a lattice point.
+fn random(x: i32, y: i32, z: i32, seed: u32) -> f32 {
+    hash(x, y, z, seed) as f32 / u32::MAX as f32
+}
+
+/// Value noise in [-1, 1], interpolating random values at integer points.
+fn value_noise(p: &Vec3, seed: u32) -> f32 {
+    let cell = p.map(f32::floor);
+    let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+    let f = (p - cell).map(|t| t * t * (3.0 - 2.0 * t));
+    let corner = |dx: i32, dy: i32, dz: i32| random(x + dx, y + dy, z + dz, seed);
+
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), f.x);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), f.x);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), f.x);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), f.x);
+    let value = lerp(lerp(x00, x10, f.y), lerp(x01, x11, f.y), f.z);
+    value * 2.0 - 1.0
+}
+
+/// Sum of `octaves` layers of value noise, each twice as fine and half as
+/// strong as the one before, scaled back to about [-1, 1].
+fn fbm(p: &Vec3, octaves: u32, seed: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut weight = 1.0;
+    let mut total = 0.0;
+    let mut point = *p;
+    for octave in 0..octaves {
+        sum += weight * value_noise(&point, seed.wrapping_add(octave));
+        total += weight;
+        weight *= 0.5;
+        point *= 2.0;
+    }
+    if total > 0.0 { sum / total } else { 0.0 }
+}
+
+/// Cross section of a crater at `d` crater radii from its center: a bowl with
+/// a flat floor and a raised rim that fades out past the edge.
+fn crater_profile(d: f32) -> f32 {
+    let rim = 0.25;
+    if d < 1.0 {
+        (d * d - 1.0).max(-0.6) + rim * smoothstep(0.6, 1.0, d)
+    } else {
+        rim * (1.0 - smoothstep(1.0, 1.4, d))
+    }
+}
+
+/// Craters scattered over a grid of unit cells, one in about half of them,
+/// each at a random spot with a random radius. Roughly in [-0.6, 0.25].
+fn craters(p: &Vec3, seed: u32) -> f32 {
+    let seed = seed.wrapping_add(0x9e3779b9);
+    let cell = p.map(f32::floor);
+    let mut height = 0.0;
+    for dz in -1..=1 {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (x, y, z) = (cell.x as i32 + dx, cell.y as i32 + dy, cell.z as i32 + dz);
+                if random(x, y, z, seed) > 0.5 {
+                    continue;
+                }
+                let center = Vec3::new(
+                    x as f32 + random(x, y, z, seed + 1),
+                    y as f32 + random(x, y, z, seed + 2),
+                    z as f32 + random(x, y, z, seed + 3),
+                );
+                let radius = 0.15 + 0.3 * random(x, y, z, seed + 4);
+                // Smaller craters are shallower
+                height += crater_profile((p - center).magnitude() / radius) * radius / 0.45;
+            }
+        }
+    }
+    height
+}
@@ -0,0 +1,42 @@
+// texture.rs
+
+use nalgebra_glm::Vec2;
+use image::ImageResult;
+use crate::color::Color;
+
+/// RGB image sampled by the material shader.
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Texture {
+    pub fn load(filename: &str) -> ImageResult<Self> {
+        let image = image::open(filename)?.to_rgb8();
+        let (width, height) = image.dimensions();
+        let pixels = image
+            .pixels()
+            .map(|p| Color::new(p[0] as f32, p[1] as f32, p[2] as f32))
+            .collect();
+        Ok(Texture { width: width as usize, height: height as usize, pixels })
+    }
+
+    /// Texture from row-major pixels, top row first.
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), width * height, "texture size doesn't match its pixels");
+        Texture { width, height, pixels }
+    }
+
+    /// Nearest texel at `uv`, wrapping outside [0, 1]. V grows downwards, as
+    /// `Obj` already flips it when loading. An empty texture samples as white,
+    /// leaving the material's own color as it is.
+    pub fn sample(&self, uv: &Vec2) -> Color {
+        if self.width == 0 || self.height == 0 {
+            return Color::new(255.0, 255.0, 255.0);
+        }
+        let x = (uv.x.rem_euclid(1.0) * self.width as f32) as usize;
+        let y = (uv.y.rem_euclid(1.0) * self.height as f32) as usize;
+        self.pixels[y.min(self.height - 1) * self.width + x.min(self.width - 1)]
+    }
+}
@@ -0,0 +1,177 @@
+// timing.rs
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Pipeline stages measured each frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Vertex,
+    Raster,
+    Shade,
+    Present,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [Stage::Vertex, Stage::Raster, Stage::Shade, Stage::Present];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Vertex => "vertex",
+            Stage::Raster => "raster",
+            Stage::Shade => "shade",
+            Stage::Present => "present",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct FrameSample {
+    stages: [Duration; 4],
+    work: Duration,
+    /// Time since the previous frame began; the first frame has none.
+    interval: Option<Duration>,
+}
+
+/// Per-stage frame timings with rolling averages over the last `window` frames.
+pub struct FrameStats {
+    window: usize,
+    history: VecDeque<FrameSample>,
+    current: FrameSample,
+    frame_start: Option<Instant>,
+}
+
+impl FrameStats {
+    pub fn new(window: usize) -> Self {
+        FrameStats {
+            window: window.max(1),
+            history: VecDeque::with_capacity(window.max(1)),
+            current: FrameSample::default(),
+            frame_start: None,
+        }
+    }
+
+    pub fn begin_frame(&mut self) {
+        let now = Instant::now();
+        self.current = FrameSample::default();
+        if let Some(previous) = self.frame_start {
+            self.current.interval = Some(now - previous);
+        }
+        self.frame_start = Some(now);
+    }
+
+    /// Adds `duration` to the given stage. A stage can be recorded several
+    /// times per frame (one per rendered object) and the times accumulate.
+    pub fn record(&mut self, stage: Stage, duration: Duration) {
+        self.current.stages[stage.index()] += duration;
+    }
+
+    /// Runs `f` and records how long it took under `stage`.
+    pub fn time<T>(&mut self, stage: Stage, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(stage, start.elapsed());
+        result
+    }
+
+    pub fn end_frame(&mut self) {
+        if let Some(start) = self.frame_start {
+            self.current.work = start.elapsed();
+        }
+        if self.history.len() == self.window {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.current);
+    }
+
+    fn average_of(&self, f: impl Fn(&FrameSample) -> Duration) -> Duration {
+        if self.history.is_empty() {
+            return Duration::ZERO;
+        }
+        let total: Duration = self.history.iter().map(f).sum();
+        total / self.history.len() as u32
+    }
+
+    /// Average time spent in `stage` per frame.
+    pub fn average(&self, stage: Stage) -> Duration {
+        self.average_of(|sample| sample.stages[stage.index()])
+    }
+
+    /// Average time spent doing work per frame, excluding the limiter's sleep.
+    pub fn average_frame_time(&self) -> Duration {
+        self.average_of(|sample| sample.work)
+    }
+
+    /// Frames per second measured from the wall-clock interval between frames.
+    pub fn fps(&self) -> f32 {
+        let intervals: Vec<Duration> = self.history.iter().filter_map(|sample| sample.interval).collect();
+        if intervals.is_empty() {
+            return 0.0;
+        }
+        let interval = intervals.iter().sum::<Duration>() / intervals.len() as u32;
+        if interval.is_zero() {
+            0.0
+        } else {
+            1.0 / interval.as_secs_f32()
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let stages: Vec<String> = Stage::ALL
+            .iter()
+            .map(|stage| format!("{} {:.1}ms", stage.name(), self.average(*stage).as_secs_f32() * 1000.0))
+            .collect();
+        format!(
+            "{:.0} fps | frame {:.1}ms | {}",
+            self.fps(),
+            self.average_frame_time().as_secs_f32() * 1000.0,
+            stages.join(" "),
+        )
+    }
+}
+
+/// Paces the main loop to a target frame rate, sleeping only for the part of
+/// the frame budget that rendering did not use.
+pub struct FrameLimiter {
+    frame_duration: Option<Duration>,
+    last_frame: Instant,
+}
+
+impl FrameLimiter {
+    /// A `target_fps` of 0 disables the limiter.
+    pub fn new(target_fps: u32) -> Self {
+        let mut limiter = FrameLimiter {
+            frame_duration: None,
+            last_frame: Instant::now(),
+        };
+        limiter.set_target_fps(target_fps);
+        limiter
+    }
+
+    pub fn set_target_fps(&mut self, target_fps: u32) {
+        self.frame_duration = if target_fps == 0 {
+            None
+        } else {
+            Some(Duration::from_secs_f64(1.0 / target_fps as f64))
+        };
+    }
+
+    /// Sleeps until the next frame is due and returns the time elapsed since
+    /// the previous call, which is the delta the simulation should advance by.
+    pub fn wait(&mut self) -> Duration {
+        if let Some(frame_duration) = self.frame_duration {
+            let elapsed = self.last_frame.elapsed();
+            if elapsed < frame_duration {
+                std::thread::sleep(frame_duration - elapsed);
+            }
+        }
+        let now = Instant::now();
+        let delta = now - self.last_frame;
+        self.last_frame = now;
+        delta
+    }
+}
@@ -2,6 +2,7 @@ use nalgebra_glm::{Vec3, dot, Vec2};
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
 use crate::color::Color;
+use crate::scene::Light;
 
 fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
 	let min_x = v1.x.min(v2.x).min(v3.x).floor() as i32;
@@ -12,7 +13,9 @@ fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i3
 	(min_x, min_y, max_x, max_y)
 }
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
+/// Rasterizes one triangle. `lights` must be in the same space as the vertex
+/// normals.
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, lights: &[Light]) -> Vec<Fragment> {
 	let mut fragments = Vec::new();
 
 	let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
@@ -20,7 +23,6 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
 	let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
 
 	let triangle_area =edge_function(&a, &b, &c);
-	let light_dir = Vec3::new(0.0, 0.0, 1.0);
 	
 
 	for y in min_y..=max_y {
@@ -36,13 +38,16 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
 				   let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
 				   let normal = normal.normalize();
 
-				   let intensity = dot(&normal, &light_dir).max(0.0);
+				   let intensity: f32 = lights.iter()
+					   .map(|light| dot(&normal, &light.direction).max(0.0) * light.intensity)
+					   .sum();
 
 				   let color = Color::new(100.0,100.0,100.0);
 
 				   let depth = a.z * w1 + b.z * w2 + c.z * w3;
 
 				   let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
+				   let tex_coords = v1.tex_coords * w1 + v2.tex_coords * w2 + v3.tex_coords * w3;
 
 				   fragments.push(Fragment::new(
 					   Vec2::new(x as f32, y as f32),
@@ -51,6 +56,7 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
 					   normal,
 					   intensity,
 					   vertex_position,
+					   tex_coords,
 				   ));
 			}
 		}
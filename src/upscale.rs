@@ -0,0 +1,140 @@
+// upscale.rs
+
+use crate::framebuffer::Framebuffer;
+
+/// Filters used to resample the rendered image to the window size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+    /// Bicubic Catmull-Rom: sharper than bilinear, with slight ringing on hard edges.
+    Bicubic,
+}
+
+impl Filter {
+    pub fn next(self) -> Self {
+        match self {
+            Filter::Nearest => Filter::Bilinear,
+            Filter::Bilinear => Filter::Bicubic,
+            Filter::Bicubic => Filter::Nearest,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Filter::Nearest => "nearest",
+            Filter::Bilinear => "bilinear",
+            Filter::Bicubic => "bicubic",
+        }
+    }
+}
+
+/// Render scales the user can step through, as a fraction of the window size.
+pub const RENDER_SCALES: [f32; 7] = [0.25, 0.5, 0.75, 1.0, 1.5, 2.0, 3.0];
+
+fn channels(color: u32) -> [f32; 3] {
+    [((color >> 16) & 0xFF) as f32, ((color >> 8) & 0xFF) as f32, (color & 0xFF) as f32]
+}
+
+fn pack(rgb: [f32; 3]) -> u32 {
+    let channel = |v: f32| v.round().clamp(0.0, 255.0) as u32;
+    (channel(rgb[0]) << 16) | (channel(rgb[1]) << 8) | channel(rgb[2])
+}
+
+fn catmull_rom_weight(x: f32) -> f32 {
+    let x = x.abs();
+    if x < 1.0 {
+        1.5 * x * x * x - 2.5 * x * x + 1.0
+    } else if x < 2.0 {
+        -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0
+    } else {
+        0.0
+    }
+}
+
+/// Average of the source pixels under the rectangle from (`left`, `top`)
+/// to (`right`, `bottom`), each weighted by how much of it is covered.
+fn box_average(src: &Framebuffer, left: f32, top: f32, right: f32, bottom: f32) -> u32 {
+    let mut rgb = [0.0; 3];
+    let mut total = 0.0;
+    for y in top.floor() as usize..(bottom.ceil() as usize).min(src.height) {
+        let wy = bottom.min(y as f32 + 1.0) - top.max(y as f32);
+        for x in left.floor() as usize..(right.ceil() as usize).min(src.width) {
+            let weight = wy * (right.min(x as f32 + 1.0) - left.max(x as f32));
+            for (out, value) in rgb.iter_mut().zip(channels(src.buffer[y * src.width + x])) {
+                *out += value * weight;
+            }
+            total += weight;
+        }
+    }
+    pack(rgb.map(|value| value / total))
+}
+
+/// Resamples `src`'s color buffer into `dst`'s, stretching it to `dst`'s size.
+/// When shrinking, the smooth filters average every source pixel a
+/// destination pixel covers instead of sampling between four of them.
+/// The depth buffer is left alone.
+pub fn resample(src: &Framebuffer, dst: &mut Framebuffer, filter: Filter) {
+    if src.width == dst.width && src.height == dst.height {
+        dst.buffer.copy_from_slice(&src.buffer);
+        return;
+    }
+
+    let scale_x = src.width as f32 / dst.width as f32;
+    let scale_y = src.height as f32 / dst.height as f32;
+    let max_x = src.width as i32 - 1;
+    let max_y = src.height as i32 - 1;
+    let shrinking = scale_x > 1.0 || scale_y > 1.0;
+    let texel = |x: i32, y: i32| src.buffer[y.clamp(0, max_y) as usize * src.width + x.clamp(0, max_x) as usize];
+
+    for y in 0..dst.height {
+        // Position of the destination pixel center in source pixel coordinates
+        let sy = (y as f32 + 0.5) * scale_y - 0.5;
+        for x in 0..dst.width {
+            let sx = (x as f32 + 0.5) * scale_x - 0.5;
+
+            let color = match filter {
+                Filter::Nearest => texel(sx.round() as i32, sy.round() as i32),
+                _ if shrinking => box_average(
+                    src,
+                    x as f32 * scale_x, y as f32 * scale_y,
+                    (x + 1) as f32 * scale_x, (y + 1) as f32 * scale_y,
+                ),
+                Filter::Bilinear => {
+                    let (x0, y0) = (sx.floor(), sy.floor());
+                    let (fx, fy) = (sx - x0, sy - y0);
+                    let (x0, y0) = (x0 as i32, y0 as i32);
+                    let c00 = channels(texel(x0, y0));
+                    let c10 = channels(texel(x0 + 1, y0));
+                    let c01 = channels(texel(x0, y0 + 1));
+                    let c11 = channels(texel(x0 + 1, y0 + 1));
+                    let mut rgb = [0.0; 3];
+                    for (i, out) in rgb.iter_mut().enumerate() {
+                        let top = c00[i] + (c10[i] - c00[i]) * fx;
+                        let bottom = c01[i] + (c11[i] - c01[i]) * fx;
+                        *out = top + (bottom - top) * fy;
+                    }
+                    pack(rgb)
+                }
+                Filter::Bicubic => {
+                    let (x0, y0) = (sx.floor(), sy.floor());
+                    let (fx, fy) = (sx - x0, sy - y0);
+                    let (x0, y0) = (x0 as i32, y0 as i32);
+                    let mut rgb = [0.0; 3];
+                    for j in -1..=2 {
+                        let wy = catmull_rom_weight(j as f32 - fy);
+                        for i in -1..=2 {
+                            let weight = wy * catmull_rom_weight(i as f32 - fx);
+                            let c = channels(texel(x0 + i, y0 + j));
+                            for (out, value) in rgb.iter_mut().zip(c) {
+                                *out += value * weight;
+                            }
+                        }
+                    }
+                    pack(rgb)
+                }
+            };
+            dst.buffer[y * dst.width + x] = color;
+        }
+    }
+}
@@ -0,0 +1,19 @@
+// golden.rs
+//
+// Runs the golden-image check (see src/golden.rs) against the references in
+// tests/golden/. `BLESS=1 cargo test --test golden` rewrites them instead.
+
+use shaders_lab4::golden;
+use shaders_lab4::normals::NormalMode;
+use shaders_lab4::standard_meshes;
+
+#[test]
+fn planets_match_golden_images() {
+    let meshes = standard_meshes(NormalMode::FromFile);
+    if std::env::var_os("BLESS").is_some() {
+        golden::bless(&meshes).expect("could not write the golden images");
+        return;
+    }
+    let passed = golden::check(&meshes).expect("could not run the golden image check");
+    assert!(passed, "rendered images differ from tests/golden, see target/golden");
+}